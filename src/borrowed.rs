@@ -0,0 +1,105 @@
+//! Coalesced structures that borrow their string table keys from the
+//! buffer they were decoded from rather than copying them
+
+use crate::shared::{CoalFile, Coalesced, Property, Section, Value};
+use std::borrow::Cow;
+
+/// Coalesced file borrowing its file paths, section names and property names
+/// from the input buffer it was decoded from using [deserialize_coalesced_ref].
+///
+/// The borrowed keys are tied to the lifetime of the input buffer so the
+/// buffer (e.g. a memory mapped file) must outlive this structure and must not
+/// be modified while it is borrowed. Keys that are not valid UTF-8 are lossily
+/// converted and owned instead. Values are always owned as they are stored
+/// huffman compressed and must be decoded.
+///
+/// Use [CoalescedRef::into_owned] to convert into a [Coalesced] that no
+/// longer borrows from the input
+///
+/// [deserialize_coalesced_ref]: crate::deserialize_coalesced_ref
+#[derive(Debug, serde::Serialize)]
+pub struct CoalescedRef<'a> {
+    /// Coalesced version
+    pub version: u32,
+    /// Files within the coalesced
+    pub files: Vec<CoalFileRef<'a>>,
+}
+
+impl CoalescedRef<'_> {
+    /// Converts this into an owned [Coalesced] copying the borrowed keys
+    pub fn into_owned(self) -> Coalesced {
+        Coalesced {
+            version: self.version,
+            files: self
+                .files
+                .into_iter()
+                .map(CoalFileRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// File within a [CoalescedRef]
+#[derive(Debug, serde::Serialize)]
+pub struct CoalFileRef<'a> {
+    /// The file path
+    pub path: Cow<'a, str>,
+    /// The file sections
+    pub sections: Vec<SectionRef<'a>>,
+}
+
+impl CoalFileRef<'_> {
+    /// Converts this into an owned [CoalFile] copying the borrowed keys
+    pub fn into_owned(self) -> CoalFile {
+        CoalFile {
+            path: self.path.into_owned(),
+            sections: self
+                .sections
+                .into_iter()
+                .map(SectionRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Section within a [CoalFileRef]
+#[derive(Debug, serde::Serialize)]
+pub struct SectionRef<'a> {
+    /// The section name
+    pub name: Cow<'a, str>,
+    /// The section properties
+    pub properties: Vec<PropertyRef<'a>>,
+}
+
+impl SectionRef<'_> {
+    /// Converts this into an owned [Section] copying the borrowed keys
+    pub fn into_owned(self) -> Section {
+        Section {
+            name: self.name.into_owned(),
+            properties: self
+                .properties
+                .into_iter()
+                .map(PropertyRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Property within a [SectionRef]
+#[derive(Debug, serde::Serialize)]
+pub struct PropertyRef<'a> {
+    /// The property name
+    pub name: Cow<'a, str>,
+    /// The property values
+    pub values: Vec<Value>,
+}
+
+impl PropertyRef<'_> {
+    /// Converts this into an owned [Property] copying the borrowed name
+    pub fn into_owned(self) -> Property {
+        Property {
+            name: self.name.into_owned(),
+            values: self.values,
+        }
+    }
+}
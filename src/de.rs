@@ -1,12 +1,16 @@
 use crate::{
-    crc32::hash_crc32,
+    borrowed::{CoalFileRef, CoalescedRef, PropertyRef, SectionRef},
+    crc32::coalesced_crc32,
     error::{DecodeError, DecodeResult},
     huffman::Huffman,
     invert_huffman_tree,
-    shared::{CoalFile, Coalesced, Property, Section, Value, ValueType, ME3_MAGIC},
-    Tlk, TlkString, TLK_MAGIC,
+    shared::{
+        value_offset_mask, Coalesced, Endian, Value, ValueType, CHECKED_MAGIC,
+        COALESCED_HEADER_SIZE, ME3_MAGIC, VALUE_TYPE_BITS,
+    },
+    Tlk, TlkString, WString, TLK_MAGIC,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
 
 /// Seekable read buffer
 pub struct ReadBuffer<'de> {
@@ -14,12 +18,24 @@ pub struct ReadBuffer<'de> {
     buffer: &'de [u8],
     /// Cursor representing the current offset within the buffer
     cursor: usize,
+    /// Byte order of the integers within the buffer
+    endian: Endian,
 }
 
 impl<'de> ReadBuffer<'de> {
     /// Creates a new [Deserializer] from the provided buffer
     pub fn new(buffer: &'de [u8]) -> Self {
-        Self { buffer, cursor: 0 }
+        Self::with_endian(buffer, Endian::Little)
+    }
+
+    /// Creates a new [Deserializer] from the provided buffer reading
+    /// integers using the provided byte order
+    pub fn with_endian(buffer: &'de [u8], endian: Endian) -> Self {
+        Self {
+            buffer,
+            cursor: 0,
+            endian,
+        }
     }
 
     /// Obtains the remaining length in bytes left of
@@ -28,9 +44,35 @@ impl<'de> ReadBuffer<'de> {
         self.buffer.len() - self.cursor
     }
 
+    /// Gets the current cursor position within the buffer
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    /// Runs the provided speculative read, if it fails the cursor is restored
+    /// to where it was before the read so that another read can be attempted
+    pub fn with_restore<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> DecodeResult<T>,
+    ) -> DecodeResult<T> {
+        let cursor = self.cursor;
+        let result = f(self);
+        if result.is_err() {
+            self.cursor = cursor;
+        }
+        result
+    }
+
+    /// Caps a count read from the buffer to the number of entries of the
+    /// provided size that could fit in the remaining bytes. Used for
+    /// capacities so that corrupt counts can't cause huge allocations
+    pub(crate) fn capacity_for(&self, count: usize, entry_size: usize) -> usize {
+        count.min(self.remaining() / entry_size)
+    }
+
     /// Internal function used to read a slice of bytes from the buffer
     pub(crate) fn read_bytes(&mut self, length: usize) -> DecodeResult<&'de [u8]> {
-        if self.cursor + length > self.buffer.len() {
+        if length > self.remaining() {
             return Err(DecodeError::UnexpectedEof {
                 cursor: self.cursor,
                 wanted: length,
@@ -69,308 +111,1151 @@ impl<'de> ReadBuffer<'de> {
     }
 
     pub fn take_slice(&mut self, length: usize) -> DecodeResult<ReadBuffer<'de>> {
-        Ok(Self::new(self.read_bytes(length)?))
+        Ok(Self::with_endian(self.read_bytes(length)?, self.endian))
     }
 
     pub fn read_u32(&mut self) -> DecodeResult<u32> {
         let bytes = self.read_fixed::<4>()?;
-        Ok(u32::from_le_bytes(bytes))
+        Ok(match self.endian {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
     }
 
     pub fn read_u16(&mut self) -> DecodeResult<u16> {
         let bytes = self.read_fixed::<2>()?;
-        Ok(u16::from_le_bytes(bytes))
+        Ok(match self.endian {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
     }
 
     pub fn read_i32(&mut self) -> DecodeResult<i32> {
         let bytes = self.read_fixed::<4>()?;
-        Ok(i32::from_le_bytes(bytes))
+        Ok(match self.endian {
+            Endian::Little => i32::from_le_bytes(bytes),
+            Endian::Big => i32::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// Options for controlling how coalesced files are deserialized
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// Whether to error if there are bytes remaining after the data block
+    /// rather than ignoring them (Some files are padded for alignment)
+    pub strict_trailing: bool,
+    /// Whether values must end with a null terminator. When disabled values
+    /// that reach the max value length or the end of the data block without
+    /// a null terminator are accepted (Some non-standard dumps omit them)
+    pub require_null_terminator: bool,
+    /// Encoding of the string table keys
+    pub encoding: Encoding,
+    /// Number of high bits of each index value entry that store the value
+    /// type (Some modded tools use more than the standard [VALUE_TYPE_BITS])
+    pub value_type_bits: u8,
+    /// Whether to check the CRC32 hash of each string table key, checking
+    /// can be skipped for faster parsing of trusted input
+    pub verify_hashes: bool,
+    /// Max length in characters of a decoded value, values that are longer
+    /// fail with [DecodeError::ValueLengthExceeded]. Unlike the max value
+    /// length from the header (Which is only a soft cap) this is always enforced
+    pub max_total_decoded: Option<usize>,
+    /// Order of the bits within each byte of the data block
+    pub bit_order: BitOrder,
+    /// Byte order of the integers within the file
+    pub endian: Endian,
+    /// Whether values with unknown type bits (Used as custom markers by some
+    /// mods) are read as [ValueType::New] rather than failing with
+    /// [DecodeError::UnknownValueType]
+    pub unknown_type_as_new: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            strict_trailing: false,
+            require_null_terminator: true,
+            encoding: Encoding::default(),
+            value_type_bits: VALUE_TYPE_BITS,
+            verify_hashes: true,
+            max_total_decoded: None,
+            bit_order: BitOrder::default(),
+            endian: Endian::default(),
+            unknown_type_as_new: false,
+        }
+    }
+}
+
+/// Diagnostics about recoverable problems found while decoding
+#[derive(Debug, Clone, Default)]
+pub struct DecodeDiagnostics {
+    /// The recoverable problems found in the order they were found
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Recoverable problem found while decoding a coalesced, see
+/// [deserialize_coalesced_verbose]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// A string table key wasn't valid UTF-8 and had invalid bytes replaced
+    LossyKey {
+        /// Offset from the start of the file to the key bytes
+        offset: usize,
+    },
+    /// There were bytes after the end of the data block
+    TrailingBytes {
+        /// Offset from the start of the file to the first trailing byte
+        offset: usize,
+        /// The number of trailing bytes
+        count: usize,
+    },
+    /// A value was longer than the max value length from the header, the header
+    /// value was ignored for the value rather than truncating it
+    MaxValueLengthExceeded {
+        /// Bit offset within the data block where the value starts
+        offset: u32,
+    },
+}
+
+/// Order that the bits of the data block are read from each byte
+#[derive(Debug, Clone, Copy, Default)]
+pub enum BitOrder {
+    /// Least significant bit first (Used by the game and when serializing)
+    #[default]
+    Lsb0,
+    /// Most significant bit first (Used by some non-BioWare tools)
+    Msb0,
+}
+
+impl BitOrder {
+    /// Gets the mask for the bit at the provided bit position within its byte
+    #[inline]
+    pub(crate) fn mask(self, position: usize) -> u8 {
+        match self {
+            BitOrder::Lsb0 => 1 << (position % 8),
+            BitOrder::Msb0 => 1 << (7 - position % 8),
+        }
+    }
+}
+
+/// Text encoding used for the string table keys
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Encoding {
+    /// Keys are UTF-8, invalid bytes are replaced which causes the
+    /// key hash check to fail
+    #[default]
+    Utf8,
+    /// Keys are Windows-1252 (Used by some ME3 tools), the key hash
+    /// is checked against the raw bytes before they are decoded
+    Windows1252,
+}
+
+impl Encoding {
+    /// Decodes the provided key bytes returning the decoded key and whether
+    /// it matches the provided hash, keys are always valid when no hash is
+    /// provided to check against
+    fn decode_key<'a>(self, bytes: &'a [u8], hash: Option<u32>) -> (Cow<'a, str>, bool) {
+        match self {
+            Encoding::Utf8 => {
+                let text: Cow<'a, str> = String::from_utf8_lossy(bytes);
+                let valid = hash.is_none_or(|hash| coalesced_crc32(text.as_bytes()) == hash);
+                (text, valid)
+            }
+            Encoding::Windows1252 => {
+                let valid = hash.is_none_or(|hash| coalesced_crc32(bytes) == hash);
+                // ASCII is the same in both encodings so it can be borrowed
+                let text: Cow<'a, str> = if bytes.is_ascii() {
+                    String::from_utf8_lossy(bytes)
+                } else {
+                    Cow::Owned(bytes.iter().copied().map(windows_1252_char).collect())
+                };
+                (text, valid)
+            }
+        }
+    }
+}
+
+/// Maps a Windows-1252 byte to its character, bytes outside of the 0x80 to 0x9F
+/// range match Latin-1. Unassigned bytes are mapped to their control characters
+fn windows_1252_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}',
+        '\u{017D}', '\u{008F}', '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}',
+        '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}',
+        '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+    ];
+
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        byte => byte as char,
     }
 }
 
+/// Header of a coalesced file describing the version and block sizes
+#[derive(Debug, Clone)]
+pub struct CoalescedHeader {
+    /// Coalesced version
+    pub version: u32,
+    /// Length of the longest string table key
+    pub max_field_name_length: u32,
+    /// Length in characters of the longest value
+    pub max_value_length: u32,
+    /// Size in bytes of the string table block
+    pub string_table_size: u32,
+    /// Size in bytes of the huffman tree block
+    pub huffman_size: u32,
+    /// Size in bytes of the index block
+    pub index_size: u32,
+    /// Size in bytes of the compressed data block
+    pub data_size: u32,
+    /// Offset from the start of the file where the string table begins
+    pub string_table_offset: usize,
+}
+
+impl CoalescedHeader {
+    /// Reads the header from the buffer leaving the cursor at the start of
+    /// the string table
+    fn read(r: &mut ReadBuffer) -> DecodeResult<Self> {
+        let magic = r.read_u32()?;
+
+        if magic != ME3_MAGIC {
+            return Err(DecodeError::UnknownFileMagic);
+        }
+
+        Ok(Self {
+            version: r.read_u32()?,
+            max_field_name_length: r.read_u32()?,
+            max_value_length: r.read_u32()?,
+            string_table_size: r.read_u32()?,
+            huffman_size: r.read_u32()?,
+            index_size: r.read_u32()?,
+            data_size: r.read_u32()?,
+            string_table_offset: COALESCED_HEADER_SIZE,
+        })
+    }
+
+    /// Total size in bytes of the file described by this header including
+    /// the header itself and the total bits field
+    pub fn total_size(&self) -> usize {
+        self.string_table_offset
+            + self.string_table_size as usize
+            + self.huffman_size as usize
+            + self.index_size as usize
+            + 4
+            + self.data_size as usize
+    }
+}
+
+/// Parses only the header of the provided coalesced without
+/// reading any of the blocks that follow it
+pub fn parse_header(input: &[u8]) -> DecodeResult<CoalescedHeader> {
+    CoalescedHeader::read(&mut ReadBuffer::new(input))
+}
+
 pub fn deserialize_coalesced(input: &[u8]) -> DecodeResult<Coalesced> {
+    deserialize_coalesced_with_options(input, &DecodeOptions::default())
+}
+
+/// Deserializes the provided coalesced using the provided options
+pub fn deserialize_coalesced_with_options(
+    input: &[u8],
+    options: &DecodeOptions,
+) -> DecodeResult<Coalesced> {
+    deserialize_coalesced_inner(input, options, true)
+        .map(|(coalesced, _, _)| coalesced.into_owned())
+}
+
+/// Deserializes the provided coalesced using the provided options also
+/// providing diagnostics and warnings about the recoverable problems that
+/// were tolerated while decoding it
+pub fn deserialize_coalesced_with_diagnostics(
+    input: &[u8],
+    options: &DecodeOptions,
+) -> DecodeResult<(Coalesced, DecodeDiagnostics)> {
+    deserialize_coalesced_inner(input, options, true)
+        .map(|(coalesced, _, diagnostics)| (coalesced.into_owned(), diagnostics))
+}
+
+/// Deserializes the provided coalesced also providing warnings about the
+/// recoverable problems that were tolerated while decoding it, see
+/// [deserialize_coalesced_with_diagnostics] for decoding with options
+pub fn deserialize_coalesced_verbose(input: &[u8]) -> DecodeResult<(Coalesced, Vec<ParseWarning>)> {
+    deserialize_coalesced_with_diagnostics(input, &DecodeOptions::default())
+        .map(|(coalesced, diagnostics)| (coalesced, diagnostics.warnings))
+}
+
+/// Reads the paths of the files within the provided coalesced without reading
+/// any of the sections or properties, no values are decoded
+pub fn coalesced_toc(input: &[u8]) -> DecodeResult<Vec<String>> {
+    let reader = CoalescedReader::new(input)?;
+    let mut index_block = ReadBuffer::with_endian(reader.index_block, reader.endian);
+    Ok(reader
+        .read_entries(&mut index_block)?
+        .into_iter()
+        .map(|(path, _)| path.into_owned())
+        .collect())
+}
+
+/// Deserializes multiple coalesced files that have been concatenated together,
+/// each file is read using the total size declared by its header. A partial
+/// file remaining at the end of the input is an error
+pub fn deserialize_coalesced_multi(input: &[u8]) -> DecodeResult<Vec<Coalesced>> {
+    let mut files: Vec<Coalesced> = Vec::new();
+    let mut remaining: &[u8] = input;
+
+    while !remaining.is_empty() {
+        let header = parse_header(remaining)?;
+        // Partial files are reported as a block size overflow when deserializing
+        let (file, rest) = remaining.split_at(header.total_size().min(remaining.len()));
+
+        files.push(deserialize_coalesced(file)?);
+        remaining = rest;
+    }
+
+    Ok(files)
+}
+
+/// Deserializes a coalesced wrapped in the checked envelope written by
+/// [serialize_coalesced_checked], the length and checksum of the coalesced
+/// bytes are verified before they are parsed
+///
+/// [serialize_coalesced_checked]: crate::serialize_coalesced_checked
+pub fn deserialize_coalesced_checked(input: &[u8]) -> DecodeResult<Coalesced> {
     let mut r = ReadBuffer::new(input);
-    // Read the file header
-    let magic = r.read_u32()?;
 
-    if magic != ME3_MAGIC {
+    let magic = r.read_u32()?;
+    if magic != CHECKED_MAGIC {
         return Err(DecodeError::UnknownFileMagic);
     }
 
-    let version = r.read_u32()?;
-    let _max_field_name_length = r.read_u32()?;
-    let max_value_length = r.read_u32()?;
-    let string_table_size = r.read_u32()?;
-    let huffman_size = r.read_u32()?;
-    let index_size = r.read_u32()?;
-    let data_size = r.read_u32()?;
+    let length = r.read_u32()?;
+    let expected = r.read_u32()?;
+    let payload = r.read_bytes(length as usize)?;
 
-    // Read the string lookup table
-    let string_table: Vec<String> = {
-        let mut string_table_block = r.take_slice(string_table_size as usize)?;
+    let actual = coalesced_crc32(payload);
+    if actual != expected {
+        return Err(DecodeError::ChecksumMismatch { expected, actual });
+    }
 
-        let local_size = string_table_block.read_u32()?;
+    deserialize_coalesced(payload)
+}
 
-        if local_size != string_table_size {
-            return Err(DecodeError::StringTableSizeMismatch);
-        }
+/// Deserializes the provided coalesced borrowing the string table keys
+/// from the input rather than copying them, see [CoalescedRef]
+pub fn deserialize_coalesced_ref(input: &[u8]) -> DecodeResult<CoalescedRef<'_>> {
+    deserialize_coalesced_inner(input, &DecodeOptions::default(), true)
+        .map(|(coalesced, _, _)| coalesced)
+}
 
-        let count = string_table_block.read_u32()?;
+/// Deserializes the provided coalesced also providing the string
+/// table keys and their CRC32 hashes in the order they are stored
+pub fn deserialize_coalesced_with_table(
+    input: &[u8],
+) -> DecodeResult<(Coalesced, Vec<(String, u32)>)> {
+    let (coalesced, string_table, _) =
+        deserialize_coalesced_inner(input, &DecodeOptions::default(), true)?;
+    let string_table = string_table
+        .into_iter()
+        .map(|(key, hash)| (key.into_owned(), hash))
+        .collect();
+    Ok((coalesced.into_owned(), string_table))
+}
 
-        let mut offsets: Vec<(u32, u32)> = Vec::new();
+/// String table keys and their CRC32 hashes in the order they are stored
+type StringTable<'a> = Vec<(Cow<'a, str>, u32)>;
 
-        for _ in 0..count {
-            let hash = string_table_block.read_u32()?;
-            let offset = string_table_block.read_u32()?;
-            offsets.push((offset, hash))
+/// Verifies the structure of the provided coalesced without decoding any of
+/// the values. The magic, block sizes, string table hashes, and the index
+/// block counts and offsets are checked which is much faster than a full
+/// deserialize
+pub fn verify_coalesced(input: &[u8]) -> DecodeResult<()> {
+    deserialize_coalesced_inner(input, &DecodeOptions::default(), false).map(|_| ())
+}
+
+/// Deserializes the coalesced structure, the text of each value is only
+/// decoded when `decode_values` is true otherwise it is left as [None]
+fn deserialize_coalesced_inner<'a>(
+    input: &'a [u8],
+    options: &DecodeOptions,
+    decode_values: bool,
+) -> DecodeResult<(CoalescedRef<'a>, StringTable<'a>, DecodeDiagnostics)> {
+    let reader = CoalescedReader::with_options(input, options)?;
+    let coalesced = reader.read_all(decode_values)?;
+    let diagnostics = reader.diagnostics();
+    Ok((coalesced, reader.string_table, diagnostics))
+}
+
+/// Deserializes only the framing of the provided coalesced, the blocks are
+/// provided as raw bytes (Apart from the huffman tree pairs) without decoding
+/// the string table, index, or values. Intended for building custom decoders
+pub fn deserialize_coalesced_raw(input: &[u8]) -> DecodeResult<RawCoalesced<'_>> {
+    RawCoalesced::read(input, false, Endian::Little)
+}
+
+/// Raw blocks of a coalesced file, see [deserialize_coalesced_raw]
+#[derive(Debug, Clone)]
+pub struct RawCoalesced<'a> {
+    /// The coalesced file header
+    pub header: CoalescedHeader,
+    /// The string table block including its size and count fields
+    pub string_table: &'a [u8],
+    /// The huffman tree pairs
+    pub huffman_tree: Vec<(i32, i32)>,
+    /// The index block describing the files, sections, and properties
+    pub index_block: &'a [u8],
+    /// The number of bits used within the data block
+    pub total_bits: u32,
+    /// The huffman compressed data block
+    pub data_block: &'a [u8],
+}
+
+impl<'a> RawCoalesced<'a> {
+    /// Reads the header and splits the input into its blocks, when `strict_trailing`
+    /// is true bytes remaining after the data block are an error
+    fn read(input: &'a [u8], strict_trailing: bool, endian: Endian) -> DecodeResult<Self> {
+        let mut r = ReadBuffer::with_endian(input, endian);
+        // Read the file header
+        let header = CoalescedHeader::read(&mut r)?;
+
+        // Ensure the declared blocks (and the total bits field) fit within the input
+        let declared: usize = header.total_size();
+
+        if declared > input.len() {
+            return Err(DecodeError::BlockSizeOverflow {
+                declared,
+                available: input.len(),
+            });
         }
 
-        let mut values = Vec::new();
-        for (offset, hash) in offsets {
-            string_table_block.seek((8 + offset) as usize)?;
+        let string_table: &[u8] = r.take_slice(header.string_table_size as usize)?.buffer;
+
+        // Read the huffman tree
+        let huffman_tree: Vec<(i32, i32)> = {
+            let mut huffman_tree_block = r.take_slice(header.huffman_size as usize)?;
+
+            // Read the length of the tree
+            let count = huffman_tree_block.read_u16()?;
 
-            let length = string_table_block.read_u16()?;
-            let bytes = string_table_block.read_bytes(length as usize)?;
-            let text: Cow<str> = String::from_utf8_lossy(bytes);
-            let text: String = text.to_string();
+            let mut values = Vec::with_capacity(huffman_tree_block.capacity_for(count as usize, 8));
 
-            if hash_crc32(text.as_bytes()) != hash {
-                return Err(DecodeError::StringTableHashMismatch);
+            for _ in 0..count {
+                let left = huffman_tree_block.read_i32()?;
+                let right = huffman_tree_block.read_i32()?;
+                values.push((left, right))
             }
 
-            values.push(text);
+            values
+        };
+
+        // Read the index block
+        let index_block: &[u8] = r.take_slice(header.index_size as usize)?.buffer;
+
+        // Read the total bits count
+        let total_bits = r.read_u32()?;
+
+        // Read the data block
+        let data_block: &[u8] = r.take_slice(header.data_size as usize)?.buffer;
+
+        let available_bits = data_block.len() * 8;
+        if total_bits as usize > available_bits {
+            return Err(DecodeError::DataBitsOverflow {
+                total_bits,
+                available_bits,
+            });
         }
 
-        values
-    };
+        if strict_trailing && r.remaining() > 0 {
+            return Err(DecodeError::TrailingBytes {
+                count: r.remaining(),
+            });
+        }
 
-    // Read the huffman tree
-    let huffman_tree: Vec<(i32, i32)> = {
-        let mut huffman_tree_block = r.take_slice(huffman_size as usize)?;
+        Ok(Self {
+            header,
+            string_table,
+            huffman_tree,
+            index_block,
+            total_bits,
+            data_block,
+        })
+    }
+}
 
-        // Read the length of the tree
-        let count = huffman_tree_block.read_u16()?;
+/// Reader for coalesced files that reads the string table, huffman tree,
+/// and index block but only decodes the values of properties as they are
+/// requested rather than decoding all at once
+pub struct CoalescedReader<'de> {
+    /// The coalesced file header
+    pub header: CoalescedHeader,
+    /// The string table keys and their hashes
+    string_table: StringTable<'de>,
+    /// The huffman tree pairs
+    huffman_tree: Vec<(i32, i32)>,
+    /// The index block describing the files, sections, and properties
+    index_block: &'de [u8],
+    /// The huffman compressed data block
+    data_block: &'de [u8],
+    /// Whether values must end with a null terminator
+    require_null_terminator: bool,
+    /// Mask for the bit offset portion of the index value entries
+    value_offset_mask: u32,
+    /// Max length of a decoded value regardless of the header max value length
+    max_total_decoded: Option<usize>,
+    /// Order of the bits within each byte of the data block
+    bit_order: BitOrder,
+    /// Whether unknown value types are read as [ValueType::New]
+    unknown_type_as_new: bool,
+    /// Byte order of the integers within the index block
+    endian: Endian,
+    /// Recoverable problems found so far
+    warnings: RefCell<Vec<ParseWarning>>,
+}
 
-        let mut values = Vec::with_capacity(count as usize);
+impl<'de> CoalescedReader<'de> {
+    /// Creates a new reader from the provided input reading the
+    /// header, string table, and huffman tree
+    pub fn new(input: &'de [u8]) -> DecodeResult<Self> {
+        Self::with_options(input, &DecodeOptions::default())
+    }
 
-        for _ in 0..count {
-            let left = huffman_tree_block.read_i32()?;
-            let right = huffman_tree_block.read_i32()?;
-            values.push((left, right))
+    /// Creates a new reader from the provided input using the provided options
+    pub fn with_options(input: &'de [u8], options: &DecodeOptions) -> DecodeResult<Self> {
+        let RawCoalesced {
+            header,
+            string_table,
+            huffman_tree,
+            index_block,
+            total_bits: _,
+            data_block,
+        } = RawCoalesced::read(input, options.strict_trailing, options.endian)?;
+
+        let mut warnings: Vec<ParseWarning> = Vec::new();
+
+        let end = header.total_size();
+        if input.len() > end {
+            warnings.push(ParseWarning::TrailingBytes {
+                offset: end,
+                count: input.len() - end,
+            });
         }
 
-        values
-    };
+        // Read the string lookup table
+        let string_table: StringTable<'de> = {
+            let mut string_table_block = ReadBuffer::with_endian(string_table, options.endian);
 
-    // Read the index block
-    let mut index_block: ReadBuffer = r.take_slice(index_size as usize)?;
+            let local_size = string_table_block.read_u32()?;
 
-    let data_block: &[u8] = {
-        // Read the total bits count
-        let _total_bits = r.read_u32()?;
+            if local_size != header.string_table_size {
+                return Err(DecodeError::StringTableSizeMismatch);
+            }
 
-        // Read the data block
-        let block = r.take_slice(data_size as usize)?;
-        block.buffer
-    };
+            let count = string_table_block.read_u32()?;
+
+            let mut offsets: Vec<(u32, u32)> = Vec::new();
+
+            for _ in 0..count {
+                let hash = string_table_block.read_u32()?;
+                let offset = string_table_block.read_u32()?;
+                offsets.push((offset, hash))
+            }
 
-    // Read the number of files
-    let files_count = index_block.read_u16()?;
+            let mut values = Vec::new();
+            for (offset, hash) in offsets {
+                string_table_block.seek(8 + offset as usize)?;
 
-    let mut files: Vec<CoalFile> = Vec::with_capacity(files_count as usize);
+                let length = string_table_block.read_u16()?;
+                let key_offset = header.string_table_offset + string_table_block.cursor;
+                let bytes = string_table_block.read_bytes(length as usize)?;
+                let (text, valid) = options
+                    .encoding
+                    .decode_key(bytes, options.verify_hashes.then_some(hash));
+
+                if !valid {
+                    return Err(DecodeError::StringTableHashMismatch);
+                }
+
+                // UTF-8 keys are only copied when invalid bytes were replaced
+                if let (Encoding::Utf8, Cow::Owned(_)) = (options.encoding, &text) {
+                    warnings.push(ParseWarning::LossyKey { offset: key_offset });
+                }
+
+                values.push((text, hash));
+            }
 
-    // Read the file offsets
-    let mut file_offsets: Vec<(String, usize)> = Vec::with_capacity(files_count as usize);
+            values
+        };
+
+        Ok(Self {
+            header,
+            string_table,
+            huffman_tree,
+            index_block,
+            data_block,
+            require_null_terminator: options.require_null_terminator,
+            value_offset_mask: value_offset_mask(options.value_type_bits),
+            max_total_decoded: options.max_total_decoded,
+            bit_order: options.bit_order,
+            unknown_type_as_new: options.unknown_type_as_new,
+            endian: options.endian,
+            warnings: RefCell::new(warnings),
+        })
+    }
 
-    for _ in 0..files_count {
-        // Read the file name and get it from the string table
-        let file_name_index = index_block.read_u16()?;
-        let file_name = string_table
-            .get(file_name_index as usize)
-            .ok_or(DecodeError::InvalidNameOffset)?;
+    /// Gets the diagnostics for the file and the values decoded so far
+    pub fn diagnostics(&self) -> DecodeDiagnostics {
+        DecodeDiagnostics {
+            warnings: self.warnings.borrow().clone(),
+        }
+    }
 
-        // Read the file offset
-        let file_offset = index_block.read_u32()?;
+    /// Decodes the values of the property within the provided file and section,
+    /// only the index entries along the path are read and only the values of
+    /// the property are decoded. [None] is returned if the property doesn't exist
+    pub fn value(
+        &self,
+        file: &str,
+        section: &str,
+        property: &str,
+    ) -> DecodeResult<Option<Vec<Value>>> {
+        self.read_property(file, section, property, None)
+    }
 
-        file_offsets.push((file_name.to_string(), file_offset as usize));
+    /// Decodes the values of the property within the provided file and section
+    /// along with the bit offset within the data block where each value starts.
+    /// [None] is returned if the property doesn't exist
+    pub fn value_with_offsets(
+        &self,
+        file: &str,
+        section: &str,
+        property: &str,
+    ) -> DecodeResult<Option<Vec<(Value, u32)>>> {
+        let mut offsets: Vec<u32> = Vec::new();
+        let values = self.read_property(file, section, property, Some(&mut offsets))?;
+        Ok(values.map(|values| values.into_iter().zip(offsets).collect()))
     }
 
-    for (file_name, file_offset) in file_offsets {
-        // Seek the index to the file
-        index_block.seek(file_offset)?;
+    /// Finds and decodes the values of a property, see [CoalescedReader::value]
+    fn read_property(
+        &self,
+        file: &str,
+        section: &str,
+        property: &str,
+        offsets: Option<&mut Vec<u32>>,
+    ) -> DecodeResult<Option<Vec<Value>>> {
+        let mut index_block = ReadBuffer::with_endian(self.index_block, self.endian);
 
-        // Read the number of sections
-        let sections_count = index_block.read_u16()?;
+        let Some(file_offset) = self.find_entry(&mut index_block, file)? else {
+            return Ok(None);
+        };
 
-        let mut sections: Vec<Section> = Vec::with_capacity(sections_count as usize);
-        let mut section_offsets: Vec<(String, usize)> = Vec::with_capacity(sections_count as usize);
+        seek_index(&mut index_block, &[file_offset])?;
 
-        for _ in 0..sections_count {
-            // Read the section name and get it from the string table
-            let section_name_index = index_block.read_u16()?;
-            let section_name = string_table
-                .get(section_name_index as usize)
-                .ok_or(DecodeError::InvalidNameOffset)?;
+        let Some(section_offset) = self.find_entry(&mut index_block, section)? else {
+            return Ok(None);
+        };
 
-            // Read the section offset
-            let section_offset = index_block.read_u32()?;
+        seek_index(&mut index_block, &[file_offset, section_offset])?;
 
-            section_offsets.push((section_name.to_string(), section_offset as usize));
-        }
+        let Some(value_offset) = self.find_entry(&mut index_block, property)? else {
+            return Ok(None);
+        };
 
-        for (section_name, section_offset) in section_offsets {
-            // Seek the index to the section
-            index_block.seek(file_offset + section_offset)?;
+        seek_index(
+            &mut index_block,
+            &[file_offset, section_offset, value_offset],
+        )?;
 
-            let values_count = index_block.read_u16()? as usize;
-            let mut properties: Vec<Property> = Vec::with_capacity(values_count);
-            let mut value_offsets: Vec<(String, usize)> = Vec::with_capacity(values_count);
+        self.read_values(&mut index_block, true, offsets).map(Some)
+    }
 
-            for _ in 0..values_count {
-                // Read the value name and get it from the string table
-                let value_name_index = index_block.read_u16()?;
-                let value_name = string_table
-                    .get(value_name_index as usize)
-                    .ok_or(DecodeError::InvalidNameOffset)?;
+    /// Reads the entire coalesced structure, the text of each value is only
+    /// decoded when `decode_values` is true otherwise it is left as [None]
+    fn read_all(&self, decode_values: bool) -> DecodeResult<CoalescedRef<'de>> {
+        let mut index_block = ReadBuffer::with_endian(self.index_block, self.endian);
+
+        // Read the file names and offsets
+        let file_entries = self.read_entries(&mut index_block)?;
+        let mut files: Vec<CoalFileRef<'de>> = Vec::with_capacity(file_entries.len());
+
+        for (file_name, file_offset) in file_entries {
+            // Seek the index to the file
+            seek_index(&mut index_block, &[file_offset])?;
+
+            // Read the section names and offsets
+            let section_entries = self.read_entries(&mut index_block)?;
+            let mut sections: Vec<SectionRef<'de>> = Vec::with_capacity(section_entries.len());
+
+            for (section_name, section_offset) in section_entries {
+                // Seek the index to the section
+                seek_index(&mut index_block, &[file_offset, section_offset])?;
+
+                // Read the property names and offsets
+                let property_entries = self.read_entries(&mut index_block)?;
+                let mut properties: Vec<PropertyRef<'de>> =
+                    Vec::with_capacity(property_entries.len());
+
+                for (property_name, value_offset) in property_entries {
+                    // Seek the index to the value
+                    seek_index(
+                        &mut index_block,
+                        &[file_offset, section_offset, value_offset],
+                    )?;
+
+                    let values = self.read_values(&mut index_block, decode_values, None)?;
+
+                    properties.push(PropertyRef {
+                        name: property_name,
+                        values,
+                    });
+                }
 
-                // Read the value offset
-                let value_offset = index_block.read_u32()?;
-                value_offsets.push((value_name.to_string(), value_offset as usize));
+                sections.push(SectionRef {
+                    name: section_name,
+                    properties,
+                });
             }
 
-            for (property_name, value_offset) in value_offsets {
-                // Seek the index to the value
-                index_block.seek(file_offset + section_offset + value_offset)?;
+            files.push(CoalFileRef {
+                path: file_name,
+                sections,
+            })
+        }
 
-                let item_count = index_block.read_u16()? as usize;
-                let mut items: Vec<Value> = Vec::with_capacity(values_count);
+        Ok(CoalescedRef {
+            version: self.header.version,
+            files,
+        })
+    }
 
-                for _ in 0..item_count {
-                    // Read the item offset
-                    let item_offset = index_block.read_u32()?;
+    /// Gets the length in bytes of the longest string table key, this can be
+    /// compared against the max field name length declared by the header
+    pub fn max_key_length(&self) -> usize {
+        self.string_table
+            .iter()
+            .map(|(key, _)| key.len())
+            .max()
+            .unwrap_or_default()
+    }
 
-                    // Split the type and offset
-                    let ty = (item_offset & 0xE0000000) >> 29;
-                    let item_offset = item_offset & 0x1fffffff;
+    /// Finds the string table keys that are never referenced by the index
+    /// block, these are only present in corrupt or hand edited files
+    pub fn unused_keys(&self) -> DecodeResult<Vec<String>> {
+        let mut used: Vec<bool> = vec![false; self.string_table.len()];
+        let mut index_block = ReadBuffer::with_endian(self.index_block, self.endian);
+
+        let mut mark_entries = |index_block: &mut ReadBuffer| -> DecodeResult<Vec<usize>> {
+            let entries = self.read_entry_indexes(index_block)?;
+            Ok(entries
+                .into_iter()
+                .map(|(name_index, offset)| {
+                    used[name_index] = true;
+                    offset
+                })
+                .collect())
+        };
+
+        for file_offset in mark_entries(&mut index_block)? {
+            seek_index(&mut index_block, &[file_offset])?;
+
+            for section_offset in mark_entries(&mut index_block)? {
+                seek_index(&mut index_block, &[file_offset, section_offset])?;
+                mark_entries(&mut index_block)?;
+            }
+        }
 
-                    let ty =
-                        ValueType::try_from(ty as u8).map_err(|_| DecodeError::UnknownValueType)?;
+        Ok(self
+            .string_table
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|((key, _), _)| key.to_string())
+            .collect())
+    }
 
-                    let text = match ty {
-                        ValueType::RemoveProperty => None,
-                        _ => {
-                            let text = Huffman::decode(
-                                data_block,
-                                &huffman_tree,
-                                item_offset as usize,
-                                max_value_length as usize,
-                            )?;
+    /// Reads a table of names and their offsets from the index block
+    fn read_entries(
+        &self,
+        index_block: &mut ReadBuffer,
+    ) -> DecodeResult<Vec<(Cow<'de, str>, usize)>> {
+        Ok(self
+            .read_entry_indexes(index_block)?
+            .into_iter()
+            .map(|(name_index, offset)| (self.string_table[name_index].0.clone(), offset))
+            .collect())
+    }
+
+    /// Reads a table of string table indexes and their offsets from the index
+    /// block, the indexes are checked to be within the string table
+    fn read_entry_indexes(
+        &self,
+        index_block: &mut ReadBuffer,
+    ) -> DecodeResult<Vec<(usize, usize)>> {
+        // Read the number of entries
+        let count = index_block.read_u16()? as usize;
+        let mut entries = Vec::with_capacity(index_block.capacity_for(count, 6));
+
+        for _ in 0..count {
+            // Read the name index and ensure it is within the string table
+            let name_index = index_block.read_u16()? as usize;
+            if name_index >= self.string_table.len() {
+                return Err(DecodeError::InvalidNameOffset);
+            }
+
+            // Read the offset
+            let offset = index_block.read_u32()?;
+
+            entries.push((name_index, offset as usize));
+        }
+
+        Ok(entries)
+    }
 
-                            Some(text)
+    /// Reads a table of names and offsets from the index block
+    /// finding the offset of the entry with the provided name, names are
+    /// matched ignoring ASCII case like [Coalesced::get]
+    fn find_entry(&self, index_block: &mut ReadBuffer, name: &str) -> DecodeResult<Option<usize>> {
+        Ok(self
+            .read_entries(index_block)?
+            .into_iter()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(name))
+            .map(|(_, offset)| offset))
+    }
+
+    /// Reads the values of a property from the index block, the text of each
+    /// value is only decoded when `decode_values` is true. The data block bit
+    /// offset of each value is pushed to `offsets` when provided
+    fn read_values(
+        &self,
+        index_block: &mut ReadBuffer,
+        decode_values: bool,
+        mut offsets: Option<&mut Vec<u32>>,
+    ) -> DecodeResult<Vec<Value>> {
+        let data_block = self.data_block;
+
+        let item_count = index_block.read_u16()? as usize;
+        let mut items: Vec<Value> = Vec::with_capacity(index_block.capacity_for(item_count, 4));
+
+        for _ in 0..item_count {
+            // Read the item offset
+            let item_offset = index_block.read_u32()?;
+
+            // Split the type and offset
+            let ty = (item_offset as u64) >> self.value_offset_mask.count_ones();
+            let item_offset = item_offset & self.value_offset_mask;
+
+            let ty = match u8::try_from(ty)
+                .ok()
+                .and_then(|ty| ValueType::try_from(ty).ok())
+            {
+                Some(ty) => ty,
+                None if self.unknown_type_as_new => ValueType::New,
+                None => return Err(DecodeError::UnknownValueType),
+            };
+
+            let text = match ty {
+                ValueType::RemoveProperty => None,
+                _ if item_offset as usize > data_block.len() * 8 => {
+                    return Err(DecodeError::InvalidOffset {
+                        offset: item_offset as usize,
+                        length: data_block.len() * 8,
+                    });
+                }
+                _ if !decode_values => None,
+                _ => {
+                    let decode = |max_length: usize| {
+                        Huffman::decode(
+                            data_block,
+                            &self.huffman_tree,
+                            item_offset as usize,
+                            max_length,
+                            self.require_null_terminator,
+                            self.bit_order,
+                        )
+                    };
+
+                    // The header max value length is only a soft cap as some packers
+                    // write the wrong value there, longer values are still decoded
+                    // up to the max length from the options
+                    let max_length = self.max_total_decoded.unwrap_or(usize::MAX);
+                    let header_max_length = (self.header.max_value_length as usize).min(max_length);
+
+                    let text = match decode(header_max_length) {
+                        Err(DecodeError::ValueLengthExceeded { .. })
+                            if header_max_length < max_length =>
+                        {
+                            self.warnings
+                                .borrow_mut()
+                                .push(ParseWarning::MaxValueLengthExceeded {
+                                    offset: item_offset,
+                                });
+                            decode(max_length)?
                         }
+                        result => result?,
                     };
 
-                    items.push(Value { ty, text });
+                    Some(text)
                 }
+            };
 
-                properties.push(Property {
-                    name: property_name,
-                    values: items,
-                });
-            }
+            items.push(Value { ty, text });
 
-            sections.push(Section {
-                name: section_name,
-                properties,
-            });
+            if let Some(offsets) = offsets.as_mut() {
+                offsets.push(item_offset);
+            }
         }
 
-        files.push(CoalFile {
-            path: file_name,
-            sections,
-        })
+        Ok(items)
+    }
+}
+
+/// Seeks the index block to the sum of the provided relative offsets, the
+/// offsets are checked to be within the index block before seeking so that
+/// corrupt offsets are reported rather than reading unrelated data
+fn seek_index(index_block: &mut ReadBuffer, offsets: &[usize]) -> DecodeResult<()> {
+    let length = index_block.buffer.len();
+    let offset = offsets
+        .iter()
+        .try_fold(0usize, |total, offset| total.checked_add(*offset));
+
+    match offset {
+        Some(offset) if offset < length => index_block.seek(offset),
+        _ => Err(DecodeError::InvalidOffset {
+            offset: offsets
+                .iter()
+                .fold(0usize, |total, offset| total.saturating_add(*offset)),
+            length,
+        }),
     }
+}
+
+/// Either kind of ME3 file that can be deserialized
+#[derive(Debug)]
+pub enum Me3File {
+    /// Coalesced file
+    Coalesced(Coalesced),
+    /// Tlk file
+    Tlk(Tlk),
+}
 
-    let coalesced = Coalesced { version, files };
+/// Deserializes either a coalesced or tlk file from the provided input
+/// detecting which kind of file it is from the file magic
+pub fn deserialize_me3(input: &[u8]) -> DecodeResult<Me3File> {
+    let magic = ReadBuffer::new(input).read_u32()?;
 
-    Ok(coalesced)
+    match magic {
+        ME3_MAGIC => deserialize_coalesced(input).map(Me3File::Coalesced),
+        TLK_MAGIC => deserialize_tlk(input).map(Me3File::Tlk),
+        _ => Err(DecodeError::UnknownFileMagic),
+    }
 }
 
 pub fn deserialize_tlk(input: &[u8]) -> DecodeResult<Tlk> {
-    let mut r = ReadBuffer::new(input);
+    let reader = TlkReader::new(input)?;
 
-    let magic = r.read_u32()?;
+    // Decode the male ref values
+    let male_values = reader
+        .iter_male()
+        .collect::<DecodeResult<Vec<TlkString>>>()?;
 
-    if magic != TLK_MAGIC {
-        return Err(DecodeError::UnknownFileMagic);
-    }
+    // Decode the female ref values
+    let female_values = reader
+        .iter_female()
+        .collect::<DecodeResult<Vec<TlkString>>>()?;
 
-    // Header block
-    let version = r.read_u32()?;
-    let min_version = r.read_u32()?;
-    let male_entry_count = r.read_u32()?;
-    let female_entry_count = r.read_u32()?;
-    let tree_node_count = r.read_u32()?;
-    let data_length = r.read_u32()?;
+    Ok(Tlk {
+        version: reader.version,
+        min_version: reader.min_version,
+        male_values,
+        female_values,
+        language: None,
+    })
+}
 
-    let mut male_refs = Vec::<(u32, u32)>::with_capacity(male_entry_count as usize);
-    let mut female_refs = Vec::<(u32, u32)>::with_capacity(female_entry_count as usize);
+/// Reader for tlk files that decodes the strings lazily as
+/// they are requested rather than decoding all at once
+pub struct TlkReader<'de> {
+    /// The tlk file version
+    pub version: u32,
+    /// The tlk file min version
+    pub min_version: u32,
+    /// The male string IDs and their bit offsets
+    male_refs: Vec<(u32, u32)>,
+    /// The female string IDs and their bit offsets
+    female_refs: Vec<(u32, u32)>,
+    /// The huffman tree used to decode the strings
+    huffman_tree: Vec<(i32, i32)>,
+    /// The huffman encoded data block
+    data_block: &'de [u8],
+    /// Strings that have already been decoded by their bit offset when caching
+    /// is enabled, male and female strings that are the same often share the
+    /// same offset
+    cache: Option<RefCell<HashMap<u32, WString>>>,
+}
 
-    // Read the male refs
-    for _ in 0..male_entry_count {
-        let left = r.read_u32()?;
-        let right = r.read_u32()?;
+impl<'de> TlkReader<'de> {
+    /// Creates a new reader from the provided input reading the
+    /// tlk header, string refs, and huffman tree
+    pub fn new(input: &'de [u8]) -> DecodeResult<Self> {
+        Self::read(input, false)
+    }
 
-        male_refs.push((left, right));
+    /// Creates a new reader that caches the decoded strings by their bit offset
+    /// so that strings requested again or shared between multiple refs are only
+    /// decoded once. The cache keeps a copy of every decoded string
+    pub fn with_cache(input: &'de [u8]) -> DecodeResult<Self> {
+        Self::read(input, true)
     }
 
-    // Read the female refs
-    for _ in 0..female_entry_count {
-        let left = r.read_u32()?;
-        let right = r.read_u32()?;
+    fn read(input: &'de [u8], cache: bool) -> DecodeResult<Self> {
+        let mut r = ReadBuffer::new(input);
+
+        let magic = r.read_u32()?;
+
+        if magic != TLK_MAGIC {
+            return Err(DecodeError::UnknownFileMagic);
+        }
+
+        // Header block
+        let version = r.read_u32()?;
+        let min_version = r.read_u32()?;
+        let male_entry_count = r.read_u32()?;
+        let female_entry_count = r.read_u32()?;
+        let tree_node_count = r.read_u32()?;
+        let data_length = r.read_u32()?;
+
+        let mut male_refs =
+            Vec::<(u32, u32)>::with_capacity(r.capacity_for(male_entry_count as usize, 8));
+        let mut female_refs =
+            Vec::<(u32, u32)>::with_capacity(r.capacity_for(female_entry_count as usize, 8));
+
+        // Read the male refs
+        for _ in 0..male_entry_count {
+            let left = r.read_u32()?;
+            let right = r.read_u32()?;
+
+            male_refs.push((left, right));
+        }
+
+        // Read the female refs
+        for _ in 0..female_entry_count {
+            let left = r.read_u32()?;
+            let right = r.read_u32()?;
+
+            female_refs.push((left, right));
+        }
 
-        female_refs.push((left, right));
+        let mut huffman_tree: Vec<(i32, i32)> =
+            Vec::with_capacity(r.capacity_for(tree_node_count as usize, 8));
+
+        // Read the huffman tree
+        for _ in 0..tree_node_count {
+            let left = r.read_i32()?;
+            let right = r.read_i32()?;
+            huffman_tree.push((left, right))
+        }
+
+        invert_huffman_tree(&mut huffman_tree);
+
+        // Read the data block
+        let data_block: &[u8] = r.take_slice(data_length as usize)?.buffer;
+
+        Ok(Self {
+            version,
+            min_version,
+            male_refs,
+            female_refs,
+            huffman_tree,
+            data_block,
+            cache: cache.then(|| RefCell::new(HashMap::new())),
+        })
     }
 
-    let mut huffman_tree: Vec<(i32, i32)> = Vec::with_capacity(tree_node_count as usize);
+    /// Gets the huffman tree pairs used to decode the strings, the last pair
+    /// is the root. These can be reused with [serialize_tlk_reusing_tree]
+    ///
+    /// [serialize_tlk_reusing_tree]: crate::serialize_tlk_reusing_tree
+    pub fn huffman_tree(&self) -> &[(i32, i32)] {
+        &self.huffman_tree
+    }
 
-    // Read the huffman tree
-    for _ in 0..tree_node_count {
-        let left = r.read_i32()?;
-        let right = r.read_i32()?;
-        huffman_tree.push((left, right))
+    /// Gets the string with the provided ID checking the male
+    /// strings first and then the female strings
+    pub fn get(&self, id: u32) -> DecodeResult<Option<WString>> {
+        match self.get_male(id)? {
+            Some(value) => Ok(Some(value)),
+            None => self.get_female(id),
+        }
     }
 
-    invert_huffman_tree(&mut huffman_tree);
+    /// Gets the male string with the provided ID
+    pub fn get_male(&self, id: u32) -> DecodeResult<Option<WString>> {
+        Self::find(&self.male_refs, id)
+            .map(|offset| self.decode(offset))
+            .transpose()
+    }
 
-    // Read the data block
-    let data_block: &[u8] = r.take_slice(data_length as usize)?.buffer;
+    /// Gets the female string with the provided ID
+    pub fn get_female(&self, id: u32) -> DecodeResult<Option<WString>> {
+        Self::find(&self.female_refs, id)
+            .map(|offset| self.decode(offset))
+            .transpose()
+    }
 
-    let mut male_values: Vec<TlkString> = Vec::with_capacity(male_refs.len());
-    let mut female_values: Vec<TlkString> = Vec::with_capacity(female_refs.len());
+    /// Iterates the male strings decoding each string as its reached
+    pub fn iter_male(&self) -> impl Iterator<Item = DecodeResult<TlkString>> + '_ {
+        self.iter_refs(&self.male_refs)
+    }
 
-    // Decode the male ref values
-    for (key, offset) in male_refs {
-        let text = Huffman::decode(data_block, &huffman_tree, offset as usize, usize::MAX)?;
-        male_values.push(TlkString {
-            id: key,
-            value: text,
-        })
+    /// Iterates the female strings decoding each string as its reached
+    pub fn iter_female(&self) -> impl Iterator<Item = DecodeResult<TlkString>> + '_ {
+        self.iter_refs(&self.female_refs)
     }
 
-    // Decode the female ref values
-    for (key, offset) in female_refs {
-        let text = Huffman::decode(data_block, &huffman_tree, offset as usize, usize::MAX)?;
-        female_values.push(TlkString {
-            id: key,
-            value: text,
+    fn iter_refs<'a>(
+        &'a self,
+        refs: &'a [(u32, u32)],
+    ) -> impl Iterator<Item = DecodeResult<TlkString>> + 'a {
+        refs.iter().map(|(id, offset)| {
+            Ok(TlkString {
+                id: *id,
+                value: self.decode(*offset)?,
+            })
         })
     }
 
-    Ok(Tlk {
-        version,
-        min_version,
-        male_values,
-        female_values,
-    })
+    /// Finds the bit offset of the string with the provided ID
+    fn find(refs: &[(u32, u32)], id: u32) -> Option<u32> {
+        refs.iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, offset)| *offset)
+    }
+
+    /// Decodes the string at the provided bit offset, strings at offsets
+    /// that have already been decoded are taken from the cache when enabled
+    fn decode(&self, offset: u32) -> DecodeResult<WString> {
+        if let Some(value) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.borrow().get(&offset).cloned())
+        {
+            return Ok(value);
+        }
+
+        let value: WString = Huffman::decode(
+            self.data_block,
+            &self.huffman_tree,
+            offset as usize,
+            usize::MAX,
+            false,
+            BitOrder::Lsb0,
+        )?;
+
+        if let Some(cache) = &self.cache {
+            cache.borrow_mut().insert(offset, value.clone());
+        }
+
+        Ok(value)
+    }
 }
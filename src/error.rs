@@ -1,6 +1,9 @@
 use std::{error::Error, fmt::Display};
 
+/// Errors that can occur while decoding, new variants may be added and some
+/// variants are only present with their cargo feature enabled
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DecodeError {
     /// Reached the end of the available bytes before
     /// a value could be obtained
@@ -13,12 +16,61 @@ pub enum DecodeError {
         remaining: usize,
     },
 
+    /// The block sizes declared in the header exceed the
+    /// length of the provided input
+    BlockSizeOverflow {
+        /// The total number of bytes the header declares
+        declared: usize,
+        /// The number of bytes actually available
+        available: usize,
+    },
+
     UnknownFileMagic,
+    /// Additional bytes were present after the end of the file
+    TrailingBytes {
+        /// The number of trailing bytes
+        count: usize,
+    },
     StringTableHashMismatch,
     StringTableSizeMismatch,
     InvalidNameOffset,
+    /// An offset read from the file points outside of the block it refers to
+    InvalidOffset {
+        /// The offset that was read
+        offset: usize,
+        /// The length of the block the offset refers to
+        length: usize,
+    },
     UnknownValueType,
     MalformedDecompressionNodes,
+    /// The end of the data block was reached before a value's null terminator
+    MissingNullTerminator,
+    /// A value was longer than the maximum value length
+    /// without reaching a null terminator
+    ValueLengthExceeded {
+        /// The maximum allowed value length
+        max_length: usize,
+    },
+    /// The total bits count is larger than the number of bits in the data block
+    DataBitsOverflow {
+        /// The declared number of bits
+        total_bits: u32,
+        /// The number of bits within the data block
+        available_bits: usize,
+    },
+    /// The checksum of a checked envelope didn't match its payload
+    ChecksumMismatch {
+        /// The checksum stored in the envelope
+        expected: u32,
+        /// The checksum of the payload
+        actual: u32,
+    },
+    /// The XML representation was malformed
+    #[cfg(feature = "xml")]
+    MalformedXml(String),
+    /// The postcard representation was malformed
+    #[cfg(feature = "postcard")]
+    Postcard(postcard::Error),
 }
 
 /// Type alias for result which could result in a Coalesced Error
@@ -42,14 +94,217 @@ impl Display for DecodeError {
                     cursor, wanted, remaining
                 )
             }
+            DecodeError::BlockSizeOverflow {
+                declared,
+                available,
+            } => {
+                write!(
+                    f,
+                    "Declared block sizes exceed input length (declared: {}, available: {})",
+                    declared, available
+                )
+            }
+            DecodeError::TrailingBytes { count } => {
+                write!(f, "Unexpected {} trailing bytes after end of file", count)
+            }
             DecodeError::UnknownFileMagic => f.write_str("Unexpected file magic bytes"),
             DecodeError::StringTableHashMismatch => f.write_str("String table hash didn't match"),
             DecodeError::StringTableSizeMismatch => f.write_str("String table size didn't match"),
             DecodeError::InvalidNameOffset => f.write_str("Invalid name offset"),
+            DecodeError::InvalidOffset { offset, length } => write!(
+                f,
+                "Offset {} is outside of the block with length {}",
+                offset, length
+            ),
             DecodeError::UnknownValueType => f.write_str("Unknown value type"),
+            DecodeError::MissingNullTerminator => {
+                f.write_str("Reached the end of the data before a null terminator")
+            }
             DecodeError::MalformedDecompressionNodes => {
                 f.write_str("Decompression nodes are malformed")
             }
+            DecodeError::ValueLengthExceeded { max_length } => {
+                write!(f, "Value exceeded max length of {}", max_length)
+            }
+            DecodeError::DataBitsOverflow {
+                total_bits,
+                available_bits,
+            } => write!(
+                f,
+                "Data block declares {} bits but only contains {} bits",
+                total_bits, available_bits
+            ),
+            DecodeError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Checksum mismatch, expected {:#010X} but payload was {:#010X}",
+                expected, actual
+            ),
+            #[cfg(feature = "xml")]
+            DecodeError::MalformedXml(message) => write!(f, "Malformed XML: {}", message),
+            #[cfg(feature = "postcard")]
+            DecodeError::Postcard(err) => write!(f, "Malformed postcard: {}", err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EncodeError {
+    /// The male or female strings of a tlk contain
+    /// more than one string with the same ID
+    DuplicateTlkId {
+        /// The duplicated ID
+        id: u32,
+    },
+    /// Two distinct string table keys share the same CRC32 hash
+    HashCollision {
+        /// The first key
+        a: String,
+        /// The second key
+        b: String,
+        /// The shared hash
+        hash: u32,
+    },
+    /// A value is in a state that cannot be serialized faithfully,
+    /// such as a [ValueType::RemoveProperty] value that has text
+    ///
+    /// [ValueType::RemoveProperty]: crate::ValueType::RemoveProperty
+    InvalidValueState {
+        /// The file containing the value
+        file: String,
+        /// The section containing the value
+        section: String,
+        /// The property containing the value
+        property: String,
+    },
+    /// The data block grew too large for a value bit offset to fit
+    /// within the offset bits of an index entry
+    OffsetTooLarge {
+        /// The bit offset of the value
+        offset: usize,
+    },
+    /// The index block grew too large for a relative offset to fit
+    /// within the offset fields of the index
+    IndexTooLarge {
+        /// The relative offset within the index block
+        offset: usize,
+    },
+    /// A file, section, or property name is empty
+    EmptyName,
+    /// A file, section, or property name is longer than the
+    /// u16 length stored in the string table allows
+    NameTooLong {
+        /// The length of the name in bytes
+        length: usize,
+    },
+    /// There are more unique names than can be referenced by the
+    /// u16 string table indexes stored in the index block
+    TooManyKeys {
+        /// The number of unique names
+        count: usize,
+    },
+    /// There are more entries in a table of the index block than
+    /// can be counted by its u16 count
+    TooManyEntries {
+        /// The kind of entries
+        kind: EntryKind,
+        /// The number of entries
+        count: usize,
+    },
+}
+
+/// Kind of entry within a table of the coalesced index block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Files of the coalesced
+    Files,
+    /// Sections of a file
+    Sections,
+    /// Properties of a section
+    Properties,
+    /// Values of a property
+    Values,
+}
+
+/// Type alias for result which could result in an Encode Error
+pub type EncodeResult<T> = Result<T, EncodeError>;
+
+/// Error implementation
+impl Error for EncodeError {}
+
+/// Display formatting implementation
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::DuplicateTlkId { id } => write!(f, "Duplicate tlk string ID: {}", id),
+            EncodeError::HashCollision { a, b, hash } => write!(
+                f,
+                "String table keys {:?} and {:?} share the hash {:#010X}",
+                a, b, hash
+            ),
+            EncodeError::InvalidValueState {
+                file,
+                section,
+                property,
+            } => write!(
+                f,
+                "Invalid value state in {} [{}] {}: RemoveProperty values cannot have text",
+                file, section, property
+            ),
+            EncodeError::OffsetTooLarge { offset } => {
+                write!(f, "Value bit offset {} is too large to encode", offset)
+            }
+            EncodeError::IndexTooLarge { offset } => {
+                write!(f, "Index offset {} is too large to encode", offset)
+            }
+            EncodeError::EmptyName => {
+                f.write_str("File, section, and property names must not be empty")
+            }
+            EncodeError::NameTooLong { length } => {
+                write!(f, "Name of length {} exceeds the max name length", length)
+            }
+            EncodeError::TooManyKeys { count } => {
+                write!(f, "Too many unique names for the string table: {}", count)
+            }
+            EncodeError::TooManyEntries { kind, count } => {
+                write!(f, "Too many {:?} for the index block: {}", kind, count)
+            }
+        }
+    }
+}
+
+/// Errors from operations that both decode and encode coalesced files
+#[derive(Debug)]
+pub enum PatchError {
+    /// One of the inputs failed to decode
+    Decode(DecodeError),
+    /// The output failed to encode
+    Encode(EncodeError),
+}
+
+/// Type alias for result which could result in a Patch Error
+pub type PatchResult<T> = Result<T, PatchError>;
+
+impl From<DecodeError> for PatchError {
+    fn from(value: DecodeError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+impl From<EncodeError> for PatchError {
+    fn from(value: EncodeError) -> Self {
+        Self::Encode(value)
+    }
+}
+
+/// Error implementation
+impl Error for PatchError {}
+
+/// Display formatting implementation
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Decode(err) => err.fmt(f),
+            PatchError::Encode(err) => err.fmt(f),
         }
     }
 }
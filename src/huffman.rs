@@ -1,11 +1,12 @@
 use bitvec::{access::BitSafeU8, order::Lsb0, vec::BitVec};
 use std::{
-    cmp::Ordering,
+    cmp::{Ordering, Reverse},
     collections::{BinaryHeap, HashMap, VecDeque},
+    fmt::Write,
     hash::Hash,
 };
 
-use crate::{error::DecodeError, WChar, WString};
+use crate::{de::BitOrder, error::DecodeError, ser::bit_to_bytes, WChar, WString};
 
 /// Represents a node/leaf within a huffman tree
 #[derive(Debug)]
@@ -27,28 +28,45 @@ impl<C> HuffmanTree<C> {
     }
 }
 
-impl<C> PartialEq for HuffmanTree<C> {
+impl<C: HuffmanChar> HuffmanTree<C> {
+    /// Gets the smallest symbol within this huffman tree node/leaf, used
+    /// to order trees that have the same frequency
+    fn symbol(&self) -> i32 {
+        match *self {
+            HuffmanTree::Node(ref left, ref right) => left.symbol().min(right.symbol()),
+            HuffmanTree::Leaf(value, _) => value.as_symbol(),
+        }
+    }
+}
+
+impl<C: HuffmanChar> PartialEq for HuffmanTree<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.frequency().eq(&other.frequency())
+        self.cmp(other).is_eq()
     }
 }
 
-impl<C> Eq for HuffmanTree<C> {}
+impl<C: HuffmanChar> Eq for HuffmanTree<C> {}
 
-impl<C> Ord for HuffmanTree<C> {
+/// Trees are ordered so that the lowest frequency is the greatest (For use
+/// in the max heap) with ties broken by the smallest symbol so that the
+/// tree built from the same frequencies is always the same
+impl<C: HuffmanChar> Ord for HuffmanTree<C> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.frequency().cmp(&other.frequency()).reverse()
+        self.frequency()
+            .cmp(&other.frequency())
+            .then_with(|| self.symbol().cmp(&other.symbol()))
+            .reverse()
     }
 }
 
-impl<C> PartialOrd for HuffmanTree<C> {
+impl<C: HuffmanChar> PartialOrd for HuffmanTree<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
 /// Map containing character frequencies to build a huffman tree from
-#[derive(Default)]
+#[derive(Default, Clone, PartialEq)]
 pub(crate) struct FrequencyMap<C: HuffmanChar>(HashMap<C, u32>);
 
 impl<C: HuffmanChar> FrequencyMap<C> {
@@ -62,6 +80,37 @@ impl<C: HuffmanChar> FrequencyMap<C> {
     pub fn push(&mut self, value: C) {
         *self.0.entry(value).or_insert(0) += 1;
     }
+
+    /// Gets the number of pairs in a huffman tree built from these frequencies
+    pub fn pair_count(&self) -> usize {
+        // Trees are padded to at least two leafs
+        self.0.len().max(2) - 1
+    }
+
+    /// Computes the total number of bits needed to encode every character pushed
+    /// to this map. Every huffman tree built from the same frequencies has the
+    /// same total so this doesn't need to build the tree
+    pub fn encoded_bits(&self) -> usize {
+        let mut heap: BinaryHeap<Reverse<u64>> =
+            self.0.values().map(|freq| Reverse(*freq as u64)).collect();
+
+        // Trees are padded to at least two leafs
+        while heap.len() < 2 {
+            heap.push(Reverse(0));
+        }
+
+        // Each merge adds one bit to the codes of every character below it
+        let mut bits: u64 = 0;
+        while heap.len() > 1 {
+            let Reverse(left) = heap.pop().unwrap();
+            let Reverse(right) = heap.pop().unwrap();
+
+            bits += left + right;
+            heap.push(Reverse(left + right));
+        }
+
+        bits as usize
+    }
 }
 
 /// Trait implemented by types that can be decoded as strings
@@ -75,9 +124,6 @@ pub trait HuffmanString: 'static {
 
     /// Appends a char to the string
     fn append_char(&mut self, value: Self::Char);
-
-    /// Gets the length of the string
-    fn len(&self) -> usize;
 }
 
 impl HuffmanString for String {
@@ -92,11 +138,6 @@ impl HuffmanString for String {
     fn append_char(&mut self, value: Self::Char) {
         self.push(value)
     }
-
-    #[inline]
-    fn len(&self) -> usize {
-        self.len()
-    }
 }
 
 impl HuffmanString for WString {
@@ -111,11 +152,6 @@ impl HuffmanString for WString {
     fn append_char(&mut self, value: Self::Char) {
         self.push(value)
     }
-
-    #[inline]
-    fn len(&self) -> usize {
-        self.len()
-    }
 }
 
 /// Trait implemented by types that can be used as an individual
@@ -141,7 +177,7 @@ impl HuffmanChar for char {
 
     #[inline]
     fn from_symbol(value: i32) -> Self {
-        value as u8 as char
+        char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
     }
 }
 
@@ -159,7 +195,58 @@ impl HuffmanChar for WChar {
     }
 }
 
+/// Renders the flattened huffman tree pairs as a Graphviz DOT graph for
+/// debugging. The last pair is treated as the root (As it is for decoding)
+/// and leaves are labeled with the character they decode to. Links to pairs
+/// outside the tree are highlighted in red
+pub fn huffman_tree_to_dot(pairs: &[(i32, i32)]) -> String {
+    let mut out = String::from("digraph huffman {\n");
+
+    // Writing to a string cannot fail so the results are ignored
+    if let Some(root) = pairs.len().checked_sub(1) {
+        _ = writeln!(out, "    n{} [label=\"root\"];", root);
+    }
+
+    for (index, (left, right)) in pairs.iter().enumerate() {
+        for (bit, value) in [(0, *left), (1, *right)] {
+            if value < 0 {
+                let symbol = -1 - value;
+                let label = match char::from_u32(symbol as u32) {
+                    Some(value) => format!("{:?}", value),
+                    None => format!("{:#X}", symbol),
+                };
+                let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+
+                _ = writeln!(
+                    out,
+                    "    l{}_{} [shape=box, label=\"{}\"];",
+                    index, bit, label
+                );
+                _ = writeln!(
+                    out,
+                    "    n{} -> l{}_{} [label=\"{}\"];",
+                    index, index, bit, bit
+                );
+            } else {
+                if value as usize >= pairs.len() {
+                    _ = writeln!(
+                        out,
+                        "    n{} [color=red, label=\"missing {}\"];",
+                        value, value
+                    );
+                }
+
+                _ = writeln!(out, "    n{} -> n{} [label=\"{}\"];", index, value, bit);
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
 /// Huffman encoding state
+#[derive(Clone)]
 pub(crate) struct Huffman<C: HuffmanChar> {
     /// Mapping from chars to their huffman encoded bits
     mapping: HashMap<C, BitVec>,
@@ -177,6 +264,47 @@ impl<C: HuffmanChar> Huffman<C> {
         Self { mapping, pairs }
     }
 
+    /// Creates a huffman encoder from existing flattened pairs where the last
+    /// pair is the root, [None] is returned if the pairs don't form a valid tree
+    pub fn from_pairs(pairs: &[(i32, i32)]) -> Option<Self> {
+        let root = pairs.len().checked_sub(1)?;
+
+        let mut mapping = HashMap::new();
+        let mut stack: Vec<(usize, BitVec)> = vec![(root, BitVec::new())];
+        // Number of pairs visited, a valid tree visits each pair once
+        let mut visited: usize = 0;
+
+        while let Some((index, prefix)) = stack.pop() {
+            visited += 1;
+            if visited > pairs.len() {
+                return None;
+            }
+
+            let (left, right) = *pairs.get(index)?;
+
+            for (bit, value) in [(false, left), (true, right)] {
+                let mut code = prefix.clone();
+                code.push(bit);
+
+                if value < 0 {
+                    mapping.insert(C::from_symbol(-1 - value), code);
+                } else {
+                    stack.push((value as usize, code));
+                }
+            }
+        }
+
+        Some(Self {
+            mapping,
+            pairs: pairs.to_vec(),
+        })
+    }
+
+    /// Checks whether the provided character can be encoded
+    pub fn contains(&self, value: C) -> bool {
+        self.mapping.contains_key(&value)
+    }
+
     /// Get a reference to the pairs for encoding
     pub fn get_pairs(&self) -> &[(i32, i32)] {
         &self.pairs
@@ -199,41 +327,32 @@ impl<C: HuffmanChar> Huffman<C> {
         output.extend(code);
     }
 
-    /// Decodes huffman encoded text
+    /// Decodes huffman encoded text, if the text exceeds the `max_length`
+    /// before reaching a null terminator an error is returned. The bit
+    /// `position` must not be past the end of the compressed data.
+    ///
+    /// When `require_null` is false text that reaches the `max_length` or the
+    /// end of the compressed data without a null terminator is accepted,
+    /// otherwise reaching the end of the data is an error. The bits of each
+    /// byte are read in the provided `bit_order`
     pub fn decode<S: HuffmanString<Char = C>>(
         compressed_data: &[u8],
         pairs: &[(i32, i32)],
         position: usize,
         max_length: usize,
+        require_null: bool,
+        bit_order: BitOrder,
     ) -> Result<S, DecodeError> {
         let mut sb = S::new();
-        let mut cur_node = pairs.len() - 1;
-        let end = compressed_data.len() * 8;
-
-        let mut pos = position;
-
-        while pos < end && sb.len() < max_length {
-            let sample = compressed_data[pos / 8] & (1 << (pos % 8));
-            let next = pairs[cur_node];
-            let next = if sample != 0 { next.1 } else { next.0 };
-
-            if next < 0 {
-                let ch = -1 - next;
-                if ch == 0 {
-                    break;
-                }
-                sb.append_char(S::Char::from_symbol(ch));
-                cur_node = pairs.len() - 1;
-            } else {
-                cur_node = next as usize;
-                if cur_node > pairs.len() {
-                    return Err(DecodeError::MalformedDecompressionNodes);
-                }
-            }
-
-            pos += 1;
-        }
-
+        decode_symbols(
+            compressed_data,
+            pairs,
+            position,
+            max_length,
+            require_null,
+            bit_order,
+            |symbol| sb.append_char(S::Char::from_symbol(symbol)),
+        )?;
         Ok(sb)
     }
 
@@ -246,9 +365,10 @@ impl<C: HuffmanChar> Huffman<C> {
             heap.push(HuffmanTree::Leaf(char, freq));
         }
 
-        // Handle empty frequencies
-        if heap.is_empty() {
-            return HuffmanTree::Leaf(C::NULL, 0);
+        // The root must be a node so empty frequencies or frequencies with a
+        // single symbol are padded with null leafs
+        while heap.len() < 2 {
+            heap.push(HuffmanTree::Leaf(C::NULL, 0));
         }
 
         // Flatten the leafs into a tree
@@ -382,3 +502,162 @@ impl<C: HuffmanChar> Huffman<C> {
             .collect()
     }
 }
+
+/// Huffman codec for coalesced value text, allows encoding and decoding text
+/// the same way the values of the data block are encoded
+pub struct HuffmanCodec {
+    /// The underlying huffman encoder
+    huffman: Huffman<char>,
+}
+
+impl HuffmanCodec {
+    /// Creates a codec from the character frequencies of the provided texts,
+    /// each text is counted with a null terminator as it is when serializing
+    pub fn new<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut freq = FrequencyMap::<char>::default();
+        for text in texts {
+            freq.push_iter(text.chars());
+            freq.push('\0');
+        }
+
+        Self {
+            huffman: Huffman::new(freq),
+        }
+    }
+
+    /// Creates a codec from existing flattened huffman tree pairs (Such as the
+    /// pairs of [RawCoalesced]), [None] is returned if the pairs aren't a valid tree
+    ///
+    /// [RawCoalesced]: crate::RawCoalesced
+    pub fn from_pairs(pairs: &[(i32, i32)]) -> Option<Self> {
+        Huffman::from_pairs(pairs).map(|huffman| Self { huffman })
+    }
+
+    /// Gets the flattened huffman tree pairs, the last pair is the root
+    pub fn pairs(&self) -> &[(i32, i32)] {
+        self.huffman.get_pairs()
+    }
+}
+
+/// Encodes the provided text followed by a null terminator using the codec
+/// providing the bits packed least significant bit first as they are within
+/// the data block along with the number of bits used. The bytes can be passed
+/// directly to [decode_string] or [huffman_decode_raw]. [None] is returned if
+/// the codec can't encode one of the characters or the null terminator
+pub fn encode_string(codec: &HuffmanCodec, text: &str) -> Option<(Vec<u8>, usize)> {
+    if !text
+        .chars()
+        .chain(['\0'])
+        .all(|ch| codec.huffman.contains(ch))
+    {
+        return None;
+    }
+
+    let mut bits: BitVec<BitSafeU8, Lsb0> = BitVec::new();
+    codec.huffman.encode(text.chars(), &mut bits);
+    codec.huffman.encode_null(&mut bits);
+    let length = bits.len();
+    Some((bit_to_bytes(bits), length))
+}
+
+/// Decodes text from the start of the provided least significant bit first
+/// packed bytes using the codec, the text must end with a null terminator
+/// such as the bytes from [encode_string]
+pub fn decode_string(codec: &HuffmanCodec, bytes: &[u8]) -> Result<String, DecodeError> {
+    Huffman::<char>::decode(bytes, codec.pairs(), 0, usize::MAX, true, BitOrder::Lsb0)
+}
+
+/// Decodes the huffman encoded value starting at the bit `position` returning
+/// the raw symbols before they are converted to characters, the null terminator
+/// is not included. Intended for values that don't store text in the usual
+/// encoding, see [Huffman::decode] for the meaning of the other arguments
+pub fn huffman_decode_raw(
+    compressed_data: &[u8],
+    pairs: &[(i32, i32)],
+    position: usize,
+    max_length: usize,
+    require_null: bool,
+    bit_order: BitOrder,
+) -> Result<Vec<i32>, DecodeError> {
+    let mut symbols: Vec<i32> = Vec::new();
+    decode_symbols(
+        compressed_data,
+        pairs,
+        position,
+        max_length,
+        require_null,
+        bit_order,
+        |symbol| symbols.push(symbol),
+    )?;
+    Ok(symbols)
+}
+
+/// Decodes the symbols of huffman encoded text passing each symbol to
+/// `push` until the null terminator is reached, see [Huffman::decode]
+fn decode_symbols(
+    compressed_data: &[u8],
+    pairs: &[(i32, i32)],
+    position: usize,
+    max_length: usize,
+    require_null: bool,
+    bit_order: BitOrder,
+    mut push: impl FnMut(i32),
+) -> Result<(), DecodeError> {
+    // Number of symbols decoded
+    let mut length: usize = 0;
+    // The root is the last pair, trees without any pairs can't decode anything
+    let root = pairs
+        .len()
+        .checked_sub(1)
+        .ok_or(DecodeError::MalformedDecompressionNodes)?;
+    let mut cur_node = root;
+    let end = compressed_data.len() * 8;
+
+    if position > end {
+        return Err(DecodeError::InvalidOffset {
+            offset: position,
+            length: end,
+        });
+    }
+
+    let mut pos = position;
+
+    loop {
+        if !require_null && length == max_length {
+            break;
+        }
+
+        if pos >= end {
+            if require_null {
+                return Err(DecodeError::MissingNullTerminator);
+            }
+            break;
+        }
+
+        let sample = compressed_data[pos / 8] & bit_order.mask(pos);
+        let next = pairs[cur_node];
+        let next = if sample != 0 { next.1 } else { next.0 };
+
+        if next < 0 {
+            let ch = -1 - next;
+            if ch == 0 {
+                break;
+            }
+            push(ch);
+            length += 1;
+            if length > max_length {
+                return Err(DecodeError::ValueLengthExceeded { max_length });
+            }
+            cur_node = root;
+        } else {
+            cur_node = next as usize;
+            if cur_node >= pairs.len() {
+                return Err(DecodeError::MalformedDecompressionNodes);
+            }
+        }
+
+        pos += 1;
+    }
+
+    Ok(())
+}
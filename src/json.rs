@@ -0,0 +1,26 @@
+//! Helpers for converting the coalesced and tlk structures to and from JSON
+
+use crate::shared::{Coalesced, Tlk};
+
+/// Converts the provided coalesced into pretty printed JSON
+pub fn coalesced_to_json_pretty(coalesced: &Coalesced) -> String {
+    // The structures only contain string keys so serializing cannot fail
+    serde_json::to_string_pretty(coalesced).expect("Failed to serialize coalesced")
+}
+
+/// Parses a coalesced from its JSON representation, malformed JSON is
+/// reported as the [serde_json::Error] rather than a decode error
+pub fn coalesced_from_json(input: &str) -> serde_json::Result<Coalesced> {
+    serde_json::from_str(input)
+}
+
+/// Converts the provided tlk into pretty printed JSON
+pub fn tlk_to_json_pretty(tlk: &Tlk) -> String {
+    // The structures only contain string keys so serializing cannot fail
+    serde_json::to_string_pretty(tlk).expect("Failed to serialize tlk")
+}
+
+/// Parses a tlk from its JSON representation, see [coalesced_from_json]
+pub fn tlk_from_json(input: &str) -> serde_json::Result<Tlk> {
+    serde_json::from_str(input)
+}
@@ -1,11 +1,48 @@
+mod borrowed;
 mod crc32;
 mod huffman;
 
 pub mod de;
 pub mod error;
+#[cfg(feature = "json")]
+pub mod json;
+mod patch;
+#[cfg(feature = "postcard")]
+pub mod postcard;
 pub mod ser;
 pub mod shared;
+#[cfg(feature = "testutil")]
+pub mod testutil;
+#[cfg(feature = "xml")]
+pub mod xml;
 
-pub use de::{deserialize_coalesced, deserialize_tlk};
-pub use ser::{serialize_coalesced, serialize_tlk};
+#[cfg(feature = "postcard")]
+pub use crate::postcard::{
+    coalesced_from_postcard, coalesced_to_postcard, tlk_from_postcard, tlk_to_postcard,
+};
+pub use borrowed::{CoalFileRef, CoalescedRef, PropertyRef, SectionRef};
+pub use crc32::coalesced_crc32;
+pub use de::{
+    coalesced_toc, deserialize_coalesced, deserialize_coalesced_checked,
+    deserialize_coalesced_multi, deserialize_coalesced_raw, deserialize_coalesced_ref,
+    deserialize_coalesced_verbose, deserialize_coalesced_with_diagnostics,
+    deserialize_coalesced_with_options, deserialize_coalesced_with_table, deserialize_me3,
+    deserialize_tlk, parse_header, verify_coalesced, BitOrder, CoalescedHeader, CoalescedReader,
+    DecodeDiagnostics, DecodeOptions, Encoding, Me3File, ParseWarning, RawCoalesced, TlkReader,
+};
+pub use huffman::{
+    decode_string, encode_string, huffman_decode_raw, huffman_tree_to_dot, HuffmanCodec,
+};
+#[cfg(feature = "json")]
+pub use json::{coalesced_from_json, coalesced_to_json_pretty, tlk_from_json, tlk_to_json_pretty};
+pub use patch::{apply_binary_patch, coalesced_binary_patch};
+pub use ser::{
+    serialize_coalesced, serialize_coalesced_checked, serialize_coalesced_with_options,
+    serialize_coalesced_with_stats, serialize_coalesced_with_version, serialize_tlk,
+    serialize_tlk_reusing_tree, serialize_tlk_unchecked, DataBlockWriter, KeyInterner,
+    SerializeOptions, SerializeStats, SerializedCoalesced, StringTable, StringTableEntry,
+    TiebreakMode,
+};
 pub use shared::*;
+#[cfg(feature = "xml")]
+pub use xml::{coalesced_from_xml, coalesced_to_xml};
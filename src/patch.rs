@@ -0,0 +1,396 @@
+//! Applying coalesced files as patches over one another using
+//! the [ValueType] of each value
+
+use crate::{
+    de::deserialize_coalesced,
+    error::PatchResult,
+    ser::serialize_coalesced,
+    shared::{CoalFile, Coalesced, Property, Section, Value, ValueType},
+};
+
+/// Creates a binary patch that when applied to `base` using [apply_binary_patch]
+/// produces the structure of `edited`. The patch is the structural patch created
+/// by [Coalesced::diff] serialized as a coalesced so it only contains the changes
+pub fn coalesced_binary_patch(base: &[u8], edited: &[u8]) -> PatchResult<Vec<u8>> {
+    let base = deserialize_coalesced(base)?;
+    let edited = deserialize_coalesced(edited)?;
+    let patch = Coalesced::diff(&base, &edited);
+    Ok(serialize_coalesced(&patch)?)
+}
+
+/// Applies a binary patch created by [coalesced_binary_patch] to the provided
+/// serialized coalesced, providing the serialized patched coalesced. The patched
+/// coalesced takes the version of the patch (i.e. the version of the edited file)
+pub fn apply_binary_patch(base: &[u8], patch: &[u8]) -> PatchResult<Vec<u8>> {
+    let mut base = deserialize_coalesced(base)?;
+    let patch = deserialize_coalesced(patch)?;
+    base.apply_patch(&patch);
+    base.version = patch.version;
+    Ok(serialize_coalesced(&base)?)
+}
+
+impl Coalesced {
+    /// Applies the provided patch over this coalesced. Files, sections and
+    /// properties are matched by name (ignoring ASCII case as they do in game,
+    /// see below for which casing is kept) and each value of the patch is
+    /// applied in order according to its [ValueType]:
+    ///
+    /// - [ValueType::New] Overwrites the existing values, multiple new values
+    ///   within the same patch property together form the replacement values
+    /// - [ValueType::Add] Appends the value
+    /// - [ValueType::AddUnique] Appends the value if no existing value has the same text
+    /// - [ValueType::Remove] Removes any existing values with the same text
+    /// - [ValueType::RemoveProperty] Removes the entire property, values after it
+    ///   within the same patch property are added to a new empty property
+    ///
+    /// Patch properties without any values clear the values of the property
+    ///
+    /// File paths, section names, and property names are matched ignoring ASCII
+    /// case. Properties that the patch replaces the values of (i.e. the first
+    /// patch value is [ValueType::New]) take the name casing used by the patch
+    ///
+    /// Missing files, sections and properties are created when the patch adds
+    /// values to them. Sections and files left empty by a removal are removed
+    pub fn apply_patch(&mut self, patch: &Coalesced) {
+        for patch_file in &patch.files {
+            let index = match self
+                .files
+                .iter()
+                .position(|file| file.path.eq_ignore_ascii_case(&patch_file.path))
+            {
+                Some(index) => index,
+                None => {
+                    if !patch_file.sections.iter().any(Section::has_additions) {
+                        continue;
+                    }
+
+                    self.files.push(CoalFile {
+                        path: patch_file.path.clone(),
+                        sections: Vec::new(),
+                    });
+                    self.files.len() - 1
+                }
+            };
+
+            if !self.files[index].apply_patch(patch_file) {
+                self.files.remove(index);
+            }
+        }
+    }
+
+    /// Creates a patch that when applied to `base` using [Coalesced::apply_patch]
+    /// produces `edited`. Only the changed files, sections and properties are
+    /// included in the patch, file paths, section names, and property names are
+    /// matched ignoring ASCII case. Properties with a name that only changed in
+    /// case are included so that the new casing is applied:
+    ///
+    /// - Properties that are new or have changed values are written with all
+    ///   their values as [ValueType::New]
+    /// - Properties changed to have no values are written without any values
+    /// - Properties that were removed are written as [ValueType::RemoveProperty]
+    ///
+    /// As changed properties are written as [ValueType::New] values the edited
+    /// coalesced is only reproduced exactly when its values are all [ValueType::New]
+    /// (i.e. the values are the result of applying the patches), otherwise the
+    /// changed properties are reproduced with the same text as [ValueType::New]
+    pub fn diff(base: &Coalesced, edited: &Coalesced) -> Coalesced {
+        let mut files: Vec<CoalFile> = Vec::new();
+
+        for edited_file in &edited.files {
+            let base_file = base
+                .files
+                .iter()
+                .find(|file| file.path.eq_ignore_ascii_case(&edited_file.path));
+            let sections = CoalFile::diff(base_file, Some(edited_file));
+            if !sections.is_empty() {
+                files.push(CoalFile {
+                    path: edited_file.path.clone(),
+                    sections,
+                });
+            }
+        }
+
+        // Files that were removed entirely
+        for base_file in &base.files {
+            if edited
+                .files
+                .iter()
+                .any(|file| file.path.eq_ignore_ascii_case(&base_file.path))
+            {
+                continue;
+            }
+
+            let sections = CoalFile::diff(Some(base_file), None);
+            if !sections.is_empty() {
+                files.push(CoalFile {
+                    path: base_file.path.clone(),
+                    sections,
+                });
+            }
+        }
+
+        Coalesced {
+            version: edited.version,
+            files,
+        }
+    }
+}
+
+impl CoalFile {
+    /// Creates the patch sections for changes between the base and edited
+    /// file, either of which may be missing
+    fn diff(base: Option<&CoalFile>, edited: Option<&CoalFile>) -> Vec<Section> {
+        let base_sections: &[Section] = base
+            .map(|file| file.sections.as_slice())
+            .unwrap_or_default();
+        let edited_sections: &[Section] = edited
+            .map(|file| file.sections.as_slice())
+            .unwrap_or_default();
+
+        let mut sections: Vec<Section> = Vec::new();
+
+        for edited_section in edited_sections {
+            let base_section = base_sections
+                .iter()
+                .find(|section| section.name.eq_ignore_ascii_case(&edited_section.name));
+            let properties = Section::diff(base_section, Some(edited_section));
+            if !properties.is_empty() {
+                sections.push(Section {
+                    name: edited_section.name.clone(),
+                    properties,
+                });
+            }
+        }
+
+        // Sections that were removed entirely
+        for base_section in base_sections {
+            if edited_sections
+                .iter()
+                .any(|section| section.name.eq_ignore_ascii_case(&base_section.name))
+            {
+                continue;
+            }
+
+            let properties = Section::diff(Some(base_section), None);
+            if !properties.is_empty() {
+                sections.push(Section {
+                    name: base_section.name.clone(),
+                    properties,
+                });
+            }
+        }
+
+        sections
+    }
+
+    /// Applies the sections of the patch file over this file, returns
+    /// false if the file was left empty by a removal
+    fn apply_patch(&mut self, patch: &CoalFile) -> bool {
+        let mut removed = false;
+
+        for patch_section in &patch.sections {
+            let index = match self
+                .sections
+                .iter()
+                .position(|section| section.name.eq_ignore_ascii_case(&patch_section.name))
+            {
+                Some(index) => index,
+                None => {
+                    if !patch_section.has_additions() {
+                        continue;
+                    }
+
+                    self.sections.push(Section {
+                        name: patch_section.name.clone(),
+                        properties: Vec::new(),
+                    });
+                    self.sections.len() - 1
+                }
+            };
+
+            if !self.sections[index].apply_patch(patch_section) {
+                self.sections.remove(index);
+                removed = true;
+            }
+        }
+
+        !(removed && self.sections.is_empty())
+    }
+}
+
+impl Section {
+    /// Applies the properties of the patch section over this section, returns
+    /// false if the section was left empty by a removal
+    fn apply_patch(&mut self, patch: &Section) -> bool {
+        let mut removed = false;
+
+        for patch_property in &patch.properties {
+            let index = match self
+                .properties
+                .iter()
+                .position(|property| property.name.eq_ignore_ascii_case(&patch_property.name))
+            {
+                Some(index) => index,
+                None => {
+                    if !patch_property.has_additions() {
+                        continue;
+                    }
+
+                    self.properties.push(Property {
+                        name: patch_property.name.clone(),
+                        values: Vec::new(),
+                    });
+                    self.properties.len() - 1
+                }
+            };
+
+            if patch_property.replaces_values() {
+                self.properties[index].name.clone_from(&patch_property.name);
+            }
+
+            if !self.properties[index].apply_patch(patch_property) {
+                self.properties.remove(index);
+                removed = true;
+            }
+        }
+
+        !(removed && self.properties.is_empty())
+    }
+
+    /// Checks whether applying this section would add any values
+    fn has_additions(&self) -> bool {
+        self.properties.iter().any(Property::has_additions)
+    }
+
+    /// Creates the patch properties for changes between the base and edited
+    /// section, either of which may be missing
+    fn diff(base: Option<&Section>, edited: Option<&Section>) -> Vec<Property> {
+        let base_properties: &[Property] = base
+            .map(|section| section.properties.as_slice())
+            .unwrap_or_default();
+        let edited_properties: &[Property] = edited
+            .map(|section| section.properties.as_slice())
+            .unwrap_or_default();
+
+        let mut properties: Vec<Property> = Vec::new();
+
+        for edited_property in edited_properties {
+            let base_property = base_properties
+                .iter()
+                .find(|property| property.name.eq_ignore_ascii_case(&edited_property.name));
+
+            let changed = match base_property {
+                Some(base_property) => {
+                    base_property.name != edited_property.name
+                        || !base_property.structurally_eq(edited_property)
+                }
+                None => true,
+            };
+
+            if !changed {
+                continue;
+            }
+
+            let values: Vec<Value> = edited_property
+                .values
+                .iter()
+                .map(|value| Value::new(ValueType::New, value.text.clone()))
+                .collect();
+
+            properties.push(Property {
+                name: edited_property.name.clone(),
+                values,
+            });
+        }
+
+        // Properties that were removed entirely
+        for base_property in base_properties {
+            if edited_properties
+                .iter()
+                .any(|property| property.name.eq_ignore_ascii_case(&base_property.name))
+            {
+                continue;
+            }
+
+            properties.push(Property {
+                name: base_property.name.clone(),
+                values: vec![Value::new(ValueType::RemoveProperty, None)],
+            });
+        }
+
+        properties
+    }
+}
+
+impl Property {
+    /// Applies the values of the patch property over this property, returns
+    /// false if the property was removed
+    fn apply_patch(&mut self, patch: &Property) -> bool {
+        if patch.values.is_empty() {
+            self.values.clear();
+            return true;
+        }
+
+        let mut removed = false;
+        let mut overwritten = false;
+
+        for value in &patch.values {
+            match value.ty {
+                ValueType::New => {
+                    if !overwritten {
+                        self.values.clear();
+                        overwritten = true;
+                    }
+                    self.values.push(value.clone());
+                }
+                ValueType::Add => self.values.push(value.clone()),
+                ValueType::AddUnique => {
+                    if !self
+                        .values
+                        .iter()
+                        .any(|existing| existing.text == value.text)
+                    {
+                        self.values.push(value.clone());
+                    }
+                }
+                ValueType::Remove => {
+                    self.values.retain(|existing| existing.text != value.text);
+                    continue;
+                }
+                ValueType::RemoveProperty => {
+                    self.values.clear();
+                    removed = true;
+                    // New values after the removal don't replace the values added after it
+                    overwritten = true;
+                    continue;
+                }
+            }
+
+            // Values added after a removal bring the property back
+            removed = false;
+        }
+
+        !removed
+    }
+
+    /// Checks whether applying this property replaces the existing values
+    /// rather than modifying them
+    fn replaces_values(&self) -> bool {
+        matches!(
+            self.values.first().map(|value| value.ty),
+            None | Some(ValueType::New | ValueType::RemoveProperty)
+        )
+    }
+
+    /// Checks whether applying this property would add any values, empty
+    /// properties are considered additions so that they are created
+    fn has_additions(&self) -> bool {
+        self.values.is_empty()
+            || self.values.iter().any(|value| {
+                matches!(
+                    value.ty,
+                    ValueType::New | ValueType::Add | ValueType::AddUnique
+                )
+            })
+    }
+}
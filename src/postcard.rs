@@ -0,0 +1,29 @@
+//! Helpers for converting the coalesced and tlk structures to and from the
+//! compact postcard binary format, useful for caching parsed files
+
+use crate::{
+    error::{DecodeError, DecodeResult},
+    shared::{Coalesced, Tlk},
+};
+
+/// Converts the provided coalesced into postcard bytes
+pub fn coalesced_to_postcard(coalesced: &Coalesced) -> Vec<u8> {
+    // The structures only contain sized fields so serializing cannot fail
+    ::postcard::to_allocvec(coalesced).expect("Failed to serialize coalesced")
+}
+
+/// Parses a coalesced from its postcard bytes
+pub fn coalesced_from_postcard(input: &[u8]) -> DecodeResult<Coalesced> {
+    ::postcard::from_bytes(input).map_err(DecodeError::Postcard)
+}
+
+/// Converts the provided tlk into postcard bytes
+pub fn tlk_to_postcard(tlk: &Tlk) -> Vec<u8> {
+    // The structures only contain sized fields so serializing cannot fail
+    ::postcard::to_allocvec(tlk).expect("Failed to serialize tlk")
+}
+
+/// Parses a tlk from its postcard bytes
+pub fn tlk_from_postcard(input: &[u8]) -> DecodeResult<Tlk> {
+    ::postcard::from_bytes(input).map_err(DecodeError::Postcard)
+}
@@ -1,12 +1,16 @@
 use crate::{
-    crc32::hash_crc32,
+    crc32::coalesced_crc32,
+    error::{EncodeError, EncodeResult, EntryKind},
     huffman::{FrequencyMap, Huffman},
     invert_huffman_tree,
-    shared::{Coalesced, ValueType, ME3_MAGIC},
+    shared::{
+        value_offset_mask, Coalesced, Endian, ValueType, CHECKED_HEADER_SIZE, CHECKED_MAGIC,
+        COALESCED_HEADER_SIZE, ME3_MAGIC, TLK_HEADER_SIZE, VALUE_TYPE_BITS,
+    },
     Tlk, WChar, TLK_MAGIC,
 };
 use bitvec::{access::BitSafeU8, order::Lsb0, store::BitStore, vec::BitVec};
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 /// Seekable buffer implementation. Can seek beyond the end of the buffer. Writes
 /// past the end of the buffer grow the underlying buffer to match
@@ -18,24 +22,43 @@ struct WriteBuffer {
     cursor: usize,
     /// The length of the buffer that has been written to
     length: usize,
+    /// Byte order to write integers in
+    endian: Endian,
 }
 
 impl WriteBuffer {
+    /// Creates an empty buffer that writes integers using the provided byte order
+    pub fn with_endian(endian: Endian) -> Self {
+        Self {
+            endian,
+            ..Default::default()
+        }
+    }
+
     pub fn into_vec(mut self) -> Vec<u8> {
         self.buffer.truncate(self.length);
         self.buffer
     }
 
     pub fn write_u32(&mut self, value: u32) {
-        self.write_slice(&value.to_le_bytes());
+        match self.endian {
+            Endian::Little => self.write_slice(&value.to_le_bytes()),
+            Endian::Big => self.write_slice(&value.to_be_bytes()),
+        }
     }
 
     pub fn write_u16(&mut self, value: u16) {
-        self.write_slice(&value.to_le_bytes());
+        match self.endian {
+            Endian::Little => self.write_slice(&value.to_le_bytes()),
+            Endian::Big => self.write_slice(&value.to_be_bytes()),
+        }
     }
 
     pub fn write_i32(&mut self, value: i32) {
-        self.write_slice(&value.to_le_bytes());
+        match self.endian {
+            Endian::Little => self.write_slice(&value.to_le_bytes()),
+            Endian::Big => self.write_slice(&value.to_be_bytes()),
+        }
     }
 
     pub fn write_slice(&mut self, value: &[u8]) {
@@ -48,6 +71,12 @@ impl WriteBuffer {
         }
     }
 
+    /// Reserves capacity for at least `additional` more bytes past the
+    /// current buffer length so that later writes don't reallocate
+    pub fn reserve(&mut self, additional: usize) {
+        self.buffer.reserve(additional);
+    }
+
     pub fn seek(&mut self, cursor: usize) {
         self.cursor = cursor;
     }
@@ -68,102 +97,394 @@ impl WriteBuffer {
 }
 
 /// Serializes the provided coalesced into bytes
-pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
-    let mut keys: HashSet<&str> = HashSet::new();
+///
+/// Keys that share the same hash are still written, readers that look up keys
+/// by their hash can't tell them apart. Use [SerializeOptions::check_collisions]
+/// to reject them instead
+pub fn serialize_coalesced(coalesced: &Coalesced) -> EncodeResult<Vec<u8>> {
+    SerializedCoalesced::new(coalesced).map(SerializedCoalesced::into_bytes)
+}
+
+/// Serializes the provided coalesced into bytes using the provided options
+pub fn serialize_coalesced_with_options(
+    coalesced: &Coalesced,
+    options: &SerializeOptions,
+) -> EncodeResult<Vec<u8>> {
+    SerializedCoalesced::with_options(coalesced, options).map(SerializedCoalesced::into_bytes)
+}
+
+/// Serializes the provided coalesced into bytes also providing
+/// statistics about the blocks that were written
+pub fn serialize_coalesced_with_stats(
+    coalesced: &Coalesced,
+) -> EncodeResult<(Vec<u8>, SerializeStats)> {
+    let serialized = SerializedCoalesced::new(coalesced)?;
+    let stats = serialized.stats.clone();
+    Ok((serialized.into_bytes(), stats))
+}
+
+/// Serializes the provided coalesced wrapped in a checked envelope that stores
+/// the length and CRC32 checksum of the coalesced bytes so that corruption can
+/// be detected before parsing. The envelope is specific to this crate and is
+/// not understood by the game, use [deserialize_coalesced_checked] to read it
+///
+/// [deserialize_coalesced_checked]: crate::deserialize_coalesced_checked
+pub fn serialize_coalesced_checked(coalesced: &Coalesced) -> EncodeResult<Vec<u8>> {
+    let payload = serialize_coalesced(coalesced)?;
+
+    let mut out = WriteBuffer::default();
+    out.reserve(CHECKED_HEADER_SIZE + payload.len());
+    out.write_u32(CHECKED_MAGIC);
+    out.write_u32(payload.len() as u32);
+    out.write_u32(coalesced_crc32(&payload));
+    out.write_slice(&payload);
+    Ok(out.into_vec())
+}
+
+/// Serializes the provided coalesced into bytes writing the provided
+/// version into the header instead of the coalesced version
+pub fn serialize_coalesced_with_version(
+    coalesced: &Coalesced,
+    version: u32,
+) -> EncodeResult<Vec<u8>> {
+    let options = SerializeOptions {
+        version: Some(version),
+        ..Default::default()
+    };
+    serialize_coalesced_with_options(coalesced, &options)
+}
+
+/// Options for controlling how coalesced files are serialized
+#[derive(Debug, Clone)]
+pub struct SerializeOptions {
+    /// How string table keys with the same CRC32 hash are ordered
+    pub key_tiebreak: TiebreakMode,
+    /// Version to write into the header instead of the coalesced version
+    pub version: Option<u32>,
+    /// Number of high bits of each index value entry that store the value
+    /// type, values with bit offsets that don't fit in the remaining bits
+    /// fail with [EncodeError::OffsetTooLarge]
+    pub value_type_bits: u8,
+    /// Largest relative offset that can be written into the index block,
+    /// larger offsets fail with [EncodeError::IndexTooLarge]. Defaults to
+    /// the largest offset the u32 offset fields can store, only lowered by
+    /// tests to reach the limit without a huge input
+    #[doc(hidden)]
+    pub max_index_offset: u32,
+    /// Byte order to write the integers of the file in
+    pub endian: Endian,
+    /// Whether distinct keys sharing the same CRC32 hash fail with
+    /// [EncodeError::HashCollision], see [Coalesced::check_collisions]
+    pub check_collisions: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            key_tiebreak: TiebreakMode::default(),
+            version: None,
+            value_type_bits: VALUE_TYPE_BITS,
+            max_index_offset: u32::MAX,
+            endian: Endian::default(),
+            check_collisions: false,
+        }
+    }
+}
+
+/// Ordering used for string table keys that share the same CRC32 hash. The
+/// keys are always sorted by their hash first as required by the format
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TiebreakMode {
+    /// Keys are kept in the order they are first used within the
+    /// files, sections, and properties
+    #[default]
+    CrcThenInsertionOrder,
+    /// Keys are ordered by their bytes
+    CrcThenLexicographic,
+}
+
+/// Serialized coalesced bytes along with the string table and huffman
+/// tree used to create them, these can be reused when serializing again
+/// using [Coalesced::serialize_incremental]
+pub struct SerializedCoalesced {
+    /// The serialized coalesced bytes
+    bytes: Vec<u8>,
+    /// Statistics about the serialized blocks
+    stats: SerializeStats,
+    /// The string table keys in the order they were written
+    keys: Vec<String>,
+    /// The encoded string table block
+    string_table: Vec<u8>,
+    /// The character frequencies the huffman tree was created from
+    frequencies: FrequencyMap<char>,
+    /// The huffman tree used to encode the values
+    huffman: Huffman<char>,
+    /// The options used when serializing
+    options: SerializeOptions,
+}
+
+impl SerializedCoalesced {
+    /// Serializes the provided coalesced from scratch
+    pub fn new(coalesced: &Coalesced) -> EncodeResult<Self> {
+        Self::with_options(coalesced, &SerializeOptions::default())
+    }
+
+    /// Serializes the provided coalesced from scratch using the provided options,
+    /// incremental serializations from this will use the same options
+    pub fn with_options(coalesced: &Coalesced, options: &SerializeOptions) -> EncodeResult<Self> {
+        serialize_coalesced_parts(coalesced, None, options)
+    }
+
+    /// Gets the serialized bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Takes the serialized bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Gets the statistics about the serialized blocks
+    pub fn stats(&self) -> &SerializeStats {
+        &self.stats
+    }
+}
+
+/// Statistics about the blocks written when serializing a coalesced
+#[derive(Debug, Clone)]
+pub struct SerializeStats {
+    /// Length in bytes of the value text before compression
+    pub raw_text_bytes: usize,
+    /// Length in bytes of the huffman compressed data block
+    pub compressed_data_bytes: usize,
+    /// Length in bytes of the string table block
+    pub string_table_bytes: usize,
+    /// Length in bytes of the index block
+    pub index_bytes: usize,
+    /// Number of keys within the string table
+    pub key_count: usize,
+}
+
+impl Coalesced {
+    /// Serializes this coalesced reusing the string table and huffman tree from
+    /// the previous serialization when the keys and character frequencies are
+    /// unchanged. Only the index and data blocks are rebuilt in that case
+    pub fn serialize_incremental(
+        &self,
+        previous: &SerializedCoalesced,
+    ) -> EncodeResult<SerializedCoalesced> {
+        serialize_coalesced_parts(self, Some(previous), &previous.options)
+    }
+
+    /// Gets the unique keys that would be written to the string table in the
+    /// order they would be written by [serialize_coalesced]
+    pub fn string_table_keys(&self) -> Vec<String> {
+        let mut interner = KeyInterner::new();
+
+        for file in &self.files {
+            let names = std::iter::once(file.path.as_str()).chain(file.sections.iter().flat_map(
+                |section| {
+                    std::iter::once(section.name.as_str()).chain(
+                        section
+                            .properties
+                            .iter()
+                            .map(|property| property.name.as_str()),
+                    )
+                },
+            ));
+
+            for name in names {
+                interner.intern(name);
+            }
+        }
+
+        interner.finalize();
+        interner.keys
+    }
+
+    /// Computes the string table that would be written by [serialize_coalesced]
+    /// with the hash and offset assigned to each key
+    pub fn compute_string_table(&self) -> StringTable {
+        let keys = self.string_table_keys();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        StringTable::new(&keys)
+    }
+
+    /// Computes the size in bytes of the serialized coalesced without
+    /// building it. The size is exact as every block size can be determined
+    /// from the keys, structure, and character frequencies alone
+    pub fn estimated_size(&self) -> usize {
+        let keys = self.collect_keys();
+        let string_table_size: usize = 8 + keys.iter().map(|key| 8 + 2 + key.len()).sum::<usize>();
+
+        let mut freq = FrequencyMap::<char>::default();
+
+        for value in self.iter_values() {
+            // Remove property text is never encoded
+            if matches!(value.ty, ValueType::RemoveProperty) {
+                continue;
+            }
+
+            // Values without text are encoded as empty strings
+            let text = value.text.unwrap_or_default();
+            freq.push_iter(text.chars());
+            freq.push('\0');
+        }
+
+        // Mirrors the offsets used when writing the index block, each table is
+        // skipped over twice so the index block contains unused space
+        let mut index_size: usize = 2 + self.files.len() * 6;
+        let mut file_offset: usize = index_size;
+
+        for file in &self.files {
+            let section_table: usize = 2 + file.sections.len() * 6;
+            index_size = index_size.max(file_offset + section_table);
+
+            let mut section_offset: usize = section_table;
+
+            for section in &file.sections {
+                let property_table: usize = 2 + section.properties.len() * 6;
+                let values: usize = section
+                    .properties
+                    .iter()
+                    .map(|property| 2 + property.values.len() * 4)
+                    .sum();
+
+                index_size = index_size.max(file_offset + section_offset + property_table + values);
+                section_offset += property_table * 2 + values;
+            }
+
+            file_offset += section_table + section_offset;
+        }
+
+        let huffman_size: usize = 2 + freq.pair_count() * 8;
+        let data_size: usize = freq.encoded_bits().div_ceil(8);
+
+        COALESCED_HEADER_SIZE + string_table_size + huffman_size + index_size + 4 + data_size
+    }
+}
+
+fn serialize_coalesced_parts(
+    coalesced: &Coalesced,
+    previous: Option<&SerializedCoalesced>,
+    options: &SerializeOptions,
+) -> EncodeResult<SerializedCoalesced> {
+    check_entry_counts(coalesced)?;
+
+    if options.check_collisions {
+        coalesced.check_collisions()?;
+    }
+
+    let mut interner = KeyInterner::new();
 
     let mut max_value_length = 0;
+    // Total length in bytes of the encoded value text
+    let mut raw_text_bytes = 0;
 
-    let huffman: Huffman<char> = {
+    let frequencies: FrequencyMap<char> = {
         let mut freq = FrequencyMap::<char>::default();
 
         // Collect all keys for the string table
         for file in &coalesced.files {
-            keys.insert(&file.path);
+            interner.intern(&file.path);
 
             for section in &file.sections {
-                keys.insert(&section.name);
+                interner.intern(&section.name);
 
                 for value in &section.properties {
-                    keys.insert(&value.name);
+                    interner.intern(&value.name);
 
                     for item in &value.values {
-                        if let Some(text) = &item.text {
+                        // Remove property text is never encoded, other values
+                        // without text are encoded as empty strings
+                        let text: Option<&str> = match item.ty {
+                            ValueType::RemoveProperty => None,
+                            _ => Some(item.text.as_deref().unwrap_or_default()),
+                        };
+
+                        if let Some(text) = text {
                             // Collect blob of values for huffman encoded data
                             freq.push_iter(text.chars());
                             freq.push('\0');
 
-                            let value_length = text.len();
+                            // Length is measured in chars to match the decoder
+                            let value_length = text.chars().count();
                             if value_length > max_value_length {
                                 max_value_length = value_length;
                             }
+
+                            raw_text_bytes += text.len();
                         }
                     }
                 }
             }
         }
 
-        Huffman::new(freq)
+        freq
     };
 
-    // Sort the keys
-    let mut keys: Vec<&str> = keys.into_iter().collect();
-    keys.sort_by_key(|a| hash_crc32(a.as_bytes()));
+    // Reuse the previous huffman tree if the frequencies are unchanged
+    let huffman: Huffman<char> = match previous {
+        Some(previous) if previous.frequencies == frequencies => previous.huffman.clone(),
+        _ => Huffman::new(frequencies.clone()),
+    };
+
+    // Reuse the previous keys if the set of keys is unchanged
+    let previous_keys: Option<&SerializedCoalesced> = previous.filter(|previous| {
+        previous.keys.len() == interner.keys.len()
+            && previous
+                .keys
+                .iter()
+                .all(|key| interner.indexes.contains_key(key.as_str()))
+    });
+
+    let keys: Vec<&str> = match previous_keys {
+        Some(previous) => previous.keys.iter().map(String::as_str).collect(),
+        None => {
+            interner.finalize_with_tiebreak(options.key_tiebreak);
+            interner.keys.iter().map(String::as_str).collect()
+        }
+    };
 
-    // Determine the max key length
+    // Mapping from each key to its index within the sorted keys, the index
+    // block can only reference keys with an index that fits in a u16
+    let key_indexes: HashMap<&str, u16> = keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let index =
+                u16::try_from(index).map_err(|_| EncodeError::TooManyKeys { count: keys.len() })?;
+            Ok((*key, index))
+        })
+        .collect::<EncodeResult<_>>()?;
+
+    // Determine the max key length, keys are prefixed with a u16 length
     let mut max_key_length = 0;
     for key in &keys {
         let key_len = key.len();
+        if key_len > u16::MAX as usize {
+            return Err(EncodeError::NameTooLong { length: key_len });
+        }
+
         if key_len > max_key_length {
             max_key_length = key_len;
         }
     }
 
-    // Build the string table buffer
-    let string_table_buffer: Vec<u8> = {
-        let mut string_table_buffer = WriteBuffer::default();
-        string_table_buffer.seek(4); // Skip writing length till later
-        string_table_buffer.write_u32(keys.len() as u32); // Total number of keys
-
-        string_table_buffer.seek(4 + 4 + (8 * keys.len()));
-
-        let mut offsets: Vec<(u32, u32)> = Vec::new();
-
-        // Write the data table
-        for key in &keys {
-            let offset = string_table_buffer.cursor as u32;
-
-            let bytes: &[u8] = key.as_bytes();
-            let bytes_len = bytes.len();
-
-            let hash = hash_crc32(bytes);
-
-            string_table_buffer.write_u16(bytes_len as u16);
-            string_table_buffer.write_slice(bytes);
-
-            offsets.push((hash, offset))
-        }
-
-        // Seek to start of table
-        string_table_buffer.seek(8);
-
-        // Write the offsets
-        for (hash, offset) in offsets {
-            string_table_buffer.write_u32(hash);
-            string_table_buffer.write_u32(offset - 8);
-        }
-
-        // Return to start and write length
-        string_table_buffer.seek(0);
-        string_table_buffer.write_u32(string_table_buffer.length as u32);
-
-        string_table_buffer.into_vec()
+    // Build the string table buffer reusing the previous table if the keys are unchanged
+    let string_table_buffer: Vec<u8> = match previous_keys {
+        Some(previous) => previous.string_table.clone(),
+        None => StringTable::new(&keys).encode(options.endian),
     };
 
     let huffman_buffer = {
-        let mut huffman_buffer: WriteBuffer = WriteBuffer::default();
+        let mut huffman_buffer: WriteBuffer = WriteBuffer::with_endian(options.endian);
 
         let pairs = huffman.get_pairs();
 
+        huffman_buffer.reserve(2 + pairs.len() * 8);
+
         //Write the length of pairs
         huffman_buffer.write_u16(pairs.len() as u16);
 
@@ -178,10 +499,21 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
 
     let huffman_size: usize = huffman_buffer.len();
 
-    let mut data_buffer: BitVec<BitSafeU8, Lsb0> = BitVec::new();
+    let mut data_buffer = DataBlockWriter::new();
+
+    // Ensures offsets within the index block fit in the offset fields
+    let index_offset = |offset: usize| -> EncodeResult<u32> {
+        if offset > options.max_index_offset as usize {
+            return Err(EncodeError::IndexTooLarge { offset });
+        }
+        Ok(offset as u32)
+    };
+
+    let offset_mask = value_offset_mask(options.value_type_bits);
+    let offset_bits = offset_mask.count_ones();
 
     let index_buffer = {
-        let mut index_buffer: WriteBuffer = WriteBuffer::default();
+        let mut index_buffer: WriteBuffer = WriteBuffer::with_endian(options.endian);
 
         let mut file_data_offset = 2 /* file counts */ + (coalesced.files.len() * 6);
 
@@ -189,10 +521,10 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
 
         for file in &coalesced.files {
             file_offsets.push((
-                keys.iter()
-                    .position(|key| key.eq(&file.path))
-                    .expect("Missing file name key") as u16,
-                file_data_offset as u32,
+                *key_indexes
+                    .get(file.path.as_str())
+                    .expect("Missing file name key"),
+                index_offset(file_data_offset)?,
             ));
 
             let mut section_data_offset = 2 + (file.sections.len() * 6);
@@ -200,10 +532,10 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
 
             for section in &file.sections {
                 section_offset.push((
-                    keys.iter()
-                        .position(|key| key.eq(&section.name))
-                        .expect("Missing section name key") as u16,
-                    section_data_offset as u32,
+                    *key_indexes
+                        .get(section.name.as_str())
+                        .expect("Missing section name key"),
+                    index_offset(section_data_offset)?,
                 ));
 
                 let mut value_data_offset = 2 + (section.properties.len() * 6);
@@ -213,29 +545,37 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
                     index_buffer.seek(file_data_offset + section_data_offset + value_data_offset);
 
                     property_offsets.push((
-                        keys.iter()
-                            .position(|key| key.eq(&property.name))
-                            .expect("Missing property name key") as u16,
-                        value_data_offset as u32,
+                        *key_indexes
+                            .get(property.name.as_str())
+                            .expect("Missing property name key"),
+                        index_offset(value_data_offset)?,
                     ));
 
                     index_buffer.write_u16(property.values.len() as u16);
                     value_data_offset += 2;
 
                     for item in &property.values {
-                        let bit_offset = data_buffer.len();
-                        let text: Option<&String> = match item.ty {
+                        let bit_offset = data_buffer.len_bits();
+                        // Values without text are written as empty strings so that
+                        // their offset doesn't point at the text of the next value
+                        let text: Option<&str> = match item.ty {
                             ValueType::RemoveProperty => None,
-                            _ => item.text.as_ref(),
+                            _ => Some(item.text.as_deref().unwrap_or_default()),
                         };
 
+                        // Ensure the offset fits without overwriting the type bits
+                        if bit_offset > offset_mask as usize {
+                            return Err(EncodeError::OffsetTooLarge { offset: bit_offset });
+                        }
+
                         // Combine the type and the offset
-                        index_buffer
-                            .write_u32(((item.ty as u8 as u32) << 29) | (bit_offset as u32));
+                        index_buffer.write_u32(
+                            (((item.ty as u8 as u64) << offset_bits) as u32) | (bit_offset as u32),
+                        );
 
                         if let Some(text) = text {
-                            huffman.encode(text.chars(), &mut data_buffer);
-                            huffman.encode_null(&mut data_buffer);
+                            huffman.encode(text.chars(), &mut data_buffer.bits);
+                            huffman.encode_null(&mut data_buffer.bits);
                         }
 
                         value_data_offset += 4;
@@ -284,16 +624,19 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
 
     let index_size: usize = index_buffer.len();
 
-    let total_bits = data_buffer.len();
-    let data_bytes = bit_to_bytes(data_buffer);
+    let total_bits = data_buffer.len_bits();
+    let data_bytes = data_buffer.finish();
     let data_size: usize = data_bytes.len();
     let string_table_length = string_table_buffer.len();
 
-    let mut out = WriteBuffer::default();
+    let mut out = WriteBuffer::with_endian(options.endian);
+    out.reserve(
+        COALESCED_HEADER_SIZE + string_table_length + huffman_size + index_size + 4 + data_size,
+    );
 
     // Write the headers
     out.write_u32(ME3_MAGIC);
-    out.write_u32(coalesced.version);
+    out.write_u32(options.version.unwrap_or(coalesced.version));
     out.write_u32(max_key_length as u32);
     out.write_u32(max_value_length as u32);
     out.write_u32(string_table_length as u32);
@@ -308,10 +651,258 @@ pub fn serialize_coalesced(coalesced: &Coalesced) -> Vec<u8> {
     out.write_u32(total_bits as u32);
     out.write_slice(&data_bytes);
 
-    out.into_vec()
+    let stats = SerializeStats {
+        raw_text_bytes,
+        compressed_data_bytes: data_size,
+        string_table_bytes: string_table_length,
+        index_bytes: index_size,
+        key_count: keys.len(),
+    };
+
+    Ok(SerializedCoalesced {
+        bytes: out.into_vec(),
+        stats,
+        keys: keys.into_iter().map(str::to_string).collect(),
+        string_table: string_table_buffer,
+        frequencies,
+        huffman,
+        options: options.clone(),
+    })
+}
+
+/// Assigns indexes to the unique string table keys. Keys are given indexes
+/// in the order they are first interned which are only provisional, once
+/// [KeyInterner::finalize] is called the keys are sorted by their CRC32 hash
+/// (As required by the format) and the indexes become the stable indexes the
+/// keys will have within the string table
+#[derive(Debug, Clone, Default)]
+pub struct KeyInterner {
+    /// The unique keys in index order
+    keys: Vec<String>,
+    /// Mapping from each key to its index
+    indexes: HashMap<String, u32>,
+    /// Whether the keys have been sorted since the last new key
+    finalized: bool,
+}
+
+impl KeyInterner {
+    /// Creates a new empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns the provided key returning its index, the index of a new key
+    /// is provisional until the interner is finalized
+    pub fn intern(&mut self, key: &str) -> u32 {
+        if let Some(index) = self.indexes.get(key) {
+            return *index;
+        }
+
+        let index = self.keys.len() as u32;
+        self.keys.push(key.to_string());
+        self.indexes.insert(key.to_string(), index);
+        self.finalized = false;
+        index
+    }
+
+    /// Sorts the keys by their CRC32 hash assigning their final indexes, keys
+    /// with the same hash are left in the order they were first interned
+    pub fn finalize(&mut self) {
+        self.finalize_with_tiebreak(TiebreakMode::default());
+    }
+
+    /// Sorts the keys using the provided tiebreak and reassigns their indexes
+    fn finalize_with_tiebreak(&mut self, tiebreak: TiebreakMode) {
+        let mut keys: Vec<&str> = self.keys.iter().map(String::as_str).collect();
+        sort_keys(&mut keys, tiebreak);
+        let keys: Vec<String> = keys.into_iter().map(str::to_string).collect();
+
+        for (index, key) in keys.iter().enumerate() {
+            if let Some(value) = self.indexes.get_mut(key) {
+                *value = index as u32;
+            }
+        }
+
+        self.keys = keys;
+        self.finalized = true;
+    }
+
+    /// Checks whether the interner has been finalized since the
+    /// last new key was interned
+    pub fn is_finalized(&self) -> bool {
+        self.finalized
+    }
+
+    /// Gets the current index of the provided key if it has been interned
+    pub fn index(&self, key: &str) -> Option<u32> {
+        self.indexes.get(key).copied()
+    }
+
+    /// Gets the interned keys in index order
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Creates the string table for the interned keys in index order,
+    /// the interner should be finalized first
+    pub fn string_table(&self) -> StringTable {
+        let keys: Vec<&str> = self.keys.iter().map(String::as_str).collect();
+        StringTable::new(&keys)
+    }
+}
+
+/// Ensures the number of entries in each table of the index block
+/// fits within the u16 counts written before each table
+fn check_entry_counts(coalesced: &Coalesced) -> EncodeResult<()> {
+    let check = |kind: EntryKind, count: usize| {
+        if count > u16::MAX as usize {
+            return Err(EncodeError::TooManyEntries { kind, count });
+        }
+        Ok(())
+    };
+
+    check(EntryKind::Files, coalesced.files.len())?;
+
+    for file in &coalesced.files {
+        check(EntryKind::Sections, file.sections.len())?;
+
+        for section in &file.sections {
+            check(EntryKind::Properties, section.properties.len())?;
+
+            for property in &section.properties {
+                check(EntryKind::Values, property.values.len())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sorts the string table keys by their hash, the sort is stable so keys with
+/// the same hash are left in insertion order unless ordered lexicographically
+fn sort_keys(keys: &mut [&str], tiebreak: TiebreakMode) {
+    match tiebreak {
+        TiebreakMode::CrcThenInsertionOrder => keys.sort_by_key(|a| coalesced_crc32(a.as_bytes())),
+        TiebreakMode::CrcThenLexicographic => {
+            keys.sort_by_key(|a| (coalesced_crc32(a.as_bytes()), *a))
+        }
+    }
+}
+
+/// String table of a coalesced with the hash and offset of each key. Keys are
+/// stored in the order they are written which is sorted by their hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringTable {
+    /// The entries of the table in the order they are written
+    entries: Vec<StringTableEntry>,
+}
+
+/// Single key within a [StringTable]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringTableEntry {
+    /// The key text
+    pub key: String,
+    /// The CRC32 hash of the key bytes
+    pub hash: u32,
+    /// Offset of the key length and bytes relative to the start of the
+    /// hash and offset pairs (8 bytes into the string table block)
+    pub offset: u32,
+}
+
+impl StringTable {
+    /// Creates a string table from the provided keys, the keys are expected
+    /// to already be sorted in the order they should be written
+    pub fn new(keys: &[&str]) -> Self {
+        // Key data starts after the pairs of hashes and offsets
+        let mut offset = 8 * keys.len();
+
+        let entries = keys
+            .iter()
+            .map(|key| {
+                let entry = StringTableEntry {
+                    key: key.to_string(),
+                    hash: coalesced_crc32(key.as_bytes()),
+                    offset: offset as u32,
+                };
+                offset += 2 + key.len();
+                entry
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Gets the entries of the table in the order they are written
+    pub fn entries(&self) -> &[StringTableEntry] {
+        &self.entries
+    }
+
+    /// Encodes the string table block
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode(Endian::Little)
+    }
+
+    /// Encodes the string table block writing integers using the provided byte order
+    fn encode(&self, endian: Endian) -> Vec<u8> {
+        let mut string_table_buffer = WriteBuffer::with_endian(endian);
+        string_table_buffer.seek(4); // Skip writing length till later
+        string_table_buffer.write_u32(self.entries.len() as u32); // Total number of keys
+
+        // Write the offsets
+        for entry in &self.entries {
+            string_table_buffer.write_u32(entry.hash);
+            string_table_buffer.write_u32(entry.offset);
+        }
+
+        // Write the data table
+        for entry in &self.entries {
+            string_table_buffer.seek(8 + entry.offset as usize);
+
+            let bytes: &[u8] = entry.key.as_bytes();
+            string_table_buffer.write_u16(bytes.len() as u16);
+            string_table_buffer.write_slice(bytes);
+        }
+
+        // Return to start and write length
+        string_table_buffer.seek(0);
+        string_table_buffer.write_u32(string_table_buffer.length as u32);
+
+        string_table_buffer.into_vec()
+    }
+}
+
+/// Writer for building huffman compressed data blocks, bits are packed least
+/// significant bit first within each byte as they are in coalesced and tlk files
+#[derive(Default)]
+pub struct DataBlockWriter {
+    /// The bits written so far
+    bits: BitVec<BitSafeU8, Lsb0>,
+}
+
+impl DataBlockWriter {
+    /// Creates a new empty writer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the bits of the provided code in order
+    pub fn push_code(&mut self, code: &[bool]) {
+        self.bits.extend(code.iter().copied());
+    }
+
+    /// Gets the number of bits written, this is the bit offset that
+    /// the next code will be written at
+    pub fn len_bits(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Converts the written bits into bytes, unused bits of the last byte are zero
+    pub fn finish(self) -> Vec<u8> {
+        bit_to_bytes(self.bits)
+    }
 }
 
-fn bit_to_bytes(mut bits: BitVec<BitSafeU8, Lsb0>) -> Vec<u8> {
+pub(crate) fn bit_to_bytes(mut bits: BitVec<BitSafeU8, Lsb0>) -> Vec<u8> {
     // Convert the bits to bytes
     bits.set_uninitialized(false);
     bits.into_vec()
@@ -320,26 +911,61 @@ fn bit_to_bytes(mut bits: BitVec<BitSafeU8, Lsb0>) -> Vec<u8> {
         .collect()
 }
 
-pub fn serialize_tlk(tlk: &Tlk) -> Vec<u8> {
-    let mut out = WriteBuffer::default();
+/// Serializes the provided tlk into bytes, validating that
+/// there are no duplicate string IDs
+pub fn serialize_tlk(tlk: &Tlk) -> EncodeResult<Vec<u8>> {
+    tlk.validate()?;
+    Ok(serialize_tlk_unchecked(tlk))
+}
 
-    let male_entry_count: u32 = tlk.male_values.len() as u32;
-    let female_entry_count: u32 = tlk.female_values.len() as u32;
+/// Serializes the provided tlk into bytes without validating it
+pub fn serialize_tlk_unchecked(tlk: &Tlk) -> Vec<u8> {
+    serialize_tlk_with_huffman(tlk, &tlk_huffman(tlk))
+}
 
-    let huffman: Huffman<WChar> = {
-        let mut freq = FrequencyMap::<WChar>::default();
+/// Serializes the provided tlk into bytes encoding the strings using an existing
+/// huffman tree (In the order provided by [TlkReader::huffman_tree]) so that the
+/// encoding of unchanged strings stays the same. A new tree is built instead if
+/// the existing tree is invalid or can't encode every character
+///
+/// [TlkReader::huffman_tree]: crate::TlkReader::huffman_tree
+pub fn serialize_tlk_reusing_tree(tlk: &Tlk, prior_tree: &[(i32, i32)]) -> EncodeResult<Vec<u8>> {
+    tlk.validate()?;
+
+    let huffman: Huffman<WChar> = Huffman::from_pairs(prior_tree)
+        .filter(|huffman| {
+            huffman.contains(0)
+                && tlk
+                    .male_values
+                    .iter()
+                    .chain(tlk.female_values.iter())
+                    .all(|value| value.value.iter().all(|char| huffman.contains(*char)))
+        })
+        .unwrap_or_else(|| tlk_huffman(tlk));
+
+    Ok(serialize_tlk_with_huffman(tlk, &huffman))
+}
 
-        // Create a frequency map for the huffman tree with all the values
-        tlk.male_values
-            .iter()
-            .chain(tlk.female_values.iter())
-            .for_each(|value| {
-                freq.push_iter(value.value.iter().copied());
-                freq.push(0)
-            });
+/// Creates the huffman tree for encoding the strings of the provided tlk
+fn tlk_huffman(tlk: &Tlk) -> Huffman<WChar> {
+    let mut freq = FrequencyMap::<WChar>::default();
 
-        Huffman::new(freq)
-    };
+    // Create a frequency map for the huffman tree with all the values
+    tlk.male_values
+        .iter()
+        .chain(tlk.female_values.iter())
+        .for_each(|value| {
+            freq.push_iter(value.value.iter().copied());
+            freq.push(0)
+        });
+
+    Huffman::new(freq)
+}
+
+/// Serializes the provided tlk into bytes using the provided huffman tree
+fn serialize_tlk_with_huffman(tlk: &Tlk, huffman: &Huffman<WChar>) -> Vec<u8> {
+    let male_entry_count: u32 = tlk.male_values.len() as u32;
+    let female_entry_count: u32 = tlk.female_values.len() as u32;
 
     let (huffman_buffer, tree_node_count) = {
         let mut huffman_buffer: WriteBuffer = WriteBuffer::default();
@@ -348,6 +974,7 @@ pub fn serialize_tlk(tlk: &Tlk) -> Vec<u8> {
         invert_huffman_tree(&mut pairs);
 
         let tree_node_count = pairs.len() as u32;
+        huffman_buffer.reserve(pairs.len() * 8);
 
         // Write the pairs
         for (left, right) in pairs {
@@ -358,25 +985,38 @@ pub fn serialize_tlk(tlk: &Tlk) -> Vec<u8> {
         (huffman_buffer.into_vec(), tree_node_count)
     };
 
-    let mut data_buffer: BitVec<BitSafeU8, Lsb0> = BitVec::new();
+    let mut data_buffer = DataBlockWriter::new();
     let mut ref_buffer = WriteBuffer::default();
+    ref_buffer.reserve((male_entry_count + female_entry_count) as usize * 8);
 
     {
+        // Bit offsets of the strings already written, identical strings (Commonly
+        // the male and female variants) share the same offset rather than being
+        // written into the data block again
+        let mut offsets: HashMap<&[WChar], usize> = HashMap::new();
+
         tlk.male_values
             .iter()
             .chain(tlk.female_values.iter())
             .for_each(|value| {
-                let bit_offset: usize = data_buffer.len();
+                let bit_offset: usize = *offsets.entry(&value.value).or_insert_with(|| {
+                    let bit_offset = data_buffer.len_bits();
 
-                huffman.encode(value.value.iter().copied(), &mut data_buffer);
-                huffman.encode_null(&mut data_buffer);
+                    huffman.encode(value.value.iter().copied(), &mut data_buffer.bits);
+                    huffman.encode_null(&mut data_buffer.bits);
+
+                    bit_offset
+                });
 
                 ref_buffer.write_u32(value.id);
                 ref_buffer.write_u32(bit_offset as u32);
             });
     }
 
-    let data_bytes = bit_to_bytes(data_buffer);
+    let data_bytes = data_buffer.finish();
+
+    let mut out = WriteBuffer::default();
+    out.reserve(TLK_HEADER_SIZE + ref_buffer.length + huffman_buffer.len() + data_bytes.len());
 
     // Write the headers
     out.write_u32(TLK_MAGIC);
@@ -1,13 +1,69 @@
+use crate::{
+    crc32::coalesced_crc32,
+    error::{EncodeError, EncodeResult},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::PathBuf,
+};
+
 /// Magic bytes for ME3
 pub const ME3_MAGIC: u32 = 0x666D726D;
 /// Magic bytes for the ME3 tlk file
 pub const TLK_MAGIC: u32 = 0x006B6C54;
 
+/// Magic bytes for the checked envelope, see [serialize_coalesced_checked]
+///
+/// [serialize_coalesced_checked]: crate::serialize_coalesced_checked
+pub const CHECKED_MAGIC: u32 = 0x4B484343;
+/// Size in bytes of the checked envelope header
+pub(crate) const CHECKED_HEADER_SIZE: usize = 12;
+
+/// Size in bytes of the coalesced file header
+pub(crate) const COALESCED_HEADER_SIZE: usize = 32;
+/// Size in bytes of the tlk file header
+pub(crate) const TLK_HEADER_SIZE: usize = 28;
+
+/// Number of high bits of each coalesced index value entry used to store the
+/// [ValueType], the remaining low bits store the bit offset of the value
+pub const VALUE_TYPE_BITS: u8 = 3;
+
+/// Gets the mask for the bit offset portion of an index value entry when the
+/// provided number of high bits are used for the type. At least 3 bits are
+/// always used so that every [ValueType] can be stored
+pub(crate) fn value_offset_mask(type_bits: u8) -> u32 {
+    let offset_bits = 32 - type_bits.clamp(VALUE_TYPE_BITS, 32) as u32;
+    ((1u64 << offset_bits) - 1) as u32
+}
+
+/// Byte order of the integers within a coalesced file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    /// Little-endian (Used by the PC version of the game)
+    #[default]
+    Little,
+    /// Big-endian (Used by the Xbox 360 and PS3 versions of the game)
+    Big,
+}
+
 pub type WChar = u16;
 pub type WString = Vec<u16>;
 
+/// Converts the provided UTF-16 string into a UTF-8 string, surrogate
+/// pairs are combined and lone surrogates are replaced with U+FFFD
+pub fn wstring_to_string(value: &[WChar]) -> String {
+    String::from_utf16_lossy(value)
+}
+
+/// Converts the provided UTF-8 string into a UTF-16 string, characters
+/// outside the basic multilingual plane are encoded as surrogate pairs
+pub fn string_to_wstring(value: &str) -> WString {
+    value.encode_utf16().collect()
+}
+
 /// Tlk file
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Tlk {
     pub version: u32,
     pub min_version: u32,
@@ -16,9 +72,32 @@ pub struct Tlk {
     pub male_values: Vec<TlkString>,
     /// Female tlk strings
     pub female_values: Vec<TlkString>,
+
+    /// Language the strings are in (e.g. "INT" from BIOGame_INT.tlk), this
+    /// is not part of the binary format so it's never serialized into or
+    /// deserialized from tlk files
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 impl Tlk {
+    /// Creates a new empty tlk with the provided versions
+    pub fn new(version: u32, min_version: u32) -> Self {
+        Self {
+            version,
+            min_version,
+            male_values: Vec::new(),
+            female_values: Vec::new(),
+            language: None,
+        }
+    }
+
+    /// Tags this tlk with the language its strings are in
+    pub fn with_language(mut self, language: String) -> Self {
+        self.language = Some(language);
+        self
+    }
+
     /// Replaces a string with the provided ID with a new value
     pub fn replace_male(&mut self, id: u32, value: WString) -> bool {
         if let Some(entry) = self.male_values.iter_mut().find(|value| value.id == id) {
@@ -57,53 +136,201 @@ impl Tlk {
         self.female_values.push(TlkString { id, value })
     }
 
-    // Replaces a string with the provided ID with a new value
+    /// Replaces a string with the provided ID with a new value, the value is
+    /// encoded as UTF-16 with characters outside the BMP as surrogate pairs
     pub fn replace_male_utf8(&mut self, id: u32, value: String) -> bool {
-        if let Some(entry) = self.male_values.iter_mut().find(|value| value.id == id) {
-            entry.value = value.encode_utf16().collect();
-            true
-        } else {
-            false
-        }
+        self.replace_male(id, string_to_wstring(&value))
     }
 
-    /// Inserts a value into the tlk attempting to replace an existing one
+    /// Inserts a value into the tlk attempting to replace an existing one, the
+    /// value is encoded as UTF-16 with characters outside the BMP as surrogate pairs
     pub fn insert_male_utf8(&mut self, id: u32, value: String) {
-        if self.replace_male_utf8(id, value.clone()) {
-            return;
+        self.insert_male(id, string_to_wstring(&value))
+    }
+
+    /// Replaces a string with the provided ID with a new value, the value is
+    /// encoded as UTF-16 with characters outside the BMP as surrogate pairs
+    pub fn replace_female_utf8(&mut self, id: u32, value: String) -> bool {
+        self.replace_female(id, string_to_wstring(&value))
+    }
+
+    /// Inserts a value into the tlk attempting to replace an existing one, the
+    /// value is encoded as UTF-16 with characters outside the BMP as surrogate pairs
+    pub fn insert_female_utf8(&mut self, id: u32, value: String) {
+        self.insert_female(id, string_to_wstring(&value))
+    }
+
+    /// Gets the male string with the provided ID
+    pub fn get_male(&self, id: u32) -> Option<&[WChar]> {
+        self.male_values
+            .iter()
+            .find(|value| value.id == id)
+            .map(|value| value.value.as_slice())
+    }
+
+    /// Gets the female string with the provided ID
+    pub fn get_female(&self, id: u32) -> Option<&[WChar]> {
+        self.female_values
+            .iter()
+            .find(|value| value.id == id)
+            .map(|value| value.value.as_slice())
+    }
+
+    /// Gets the male string with the provided ID converted to UTF-8
+    pub fn get_male_utf8(&self, id: u32) -> Option<String> {
+        self.get_male(id).map(wstring_to_string)
+    }
+
+    /// Gets the female string with the provided ID converted to UTF-8
+    pub fn get_female_utf8(&self, id: u32) -> Option<String> {
+        self.get_female(id).map(wstring_to_string)
+    }
+
+    /// Removes the male string with the provided ID, the order of
+    /// the remaining strings is preserved
+    pub fn remove_male(&mut self, id: u32) -> bool {
+        let length = self.male_values.len();
+        self.male_values.retain(|value| value.id != id);
+        self.male_values.len() != length
+    }
+
+    /// Removes the female string with the provided ID, the order of
+    /// the remaining strings is preserved
+    pub fn remove_female(&mut self, id: u32) -> bool {
+        let length = self.female_values.len();
+        self.female_values.retain(|value| value.id != id);
+        self.female_values.len() != length
+    }
+
+    /// Validates that neither the male or female strings contain more
+    /// than one string with the same ID
+    pub fn validate(&self) -> EncodeResult<()> {
+        for values in [&self.male_values, &self.female_values] {
+            let mut ids: HashSet<u32> = HashSet::with_capacity(values.len());
+            for value in values {
+                if !ids.insert(value.id) {
+                    return Err(EncodeError::DuplicateTlkId { id: value.id });
+                }
+            }
         }
 
-        self.male_values.push(TlkString {
-            id,
-            value: value.encode_utf16().collect(),
-        })
+        Ok(())
     }
 
-    /// Replaces a string with the provided ID with a new value
-    pub fn replace_female_utf8(&mut self, id: u32, value: String) -> bool {
-        if let Some(entry) = self.female_values.iter_mut().find(|value| value.id == id) {
-            entry.value = value.encode_utf16().collect();
-            true
-        } else {
-            false
+    /// Gets the male strings sorted by ID, the stored order is left unchanged
+    pub fn sorted_male(&self) -> Vec<&TlkString> {
+        let mut values: Vec<&TlkString> = self.male_values.iter().collect();
+        values.sort_by_key(|value| value.id);
+        values
+    }
+
+    /// Gets the female strings sorted by ID, the stored order is left unchanged
+    pub fn sorted_female(&self) -> Vec<&TlkString> {
+        let mut values: Vec<&TlkString> = self.female_values.iter().collect();
+        values.sort_by_key(|value| value.id);
+        values
+    }
+
+    /// Sorts both the male and female strings by ID, the strings are
+    /// serialized in the order they are stored
+    pub fn sort(&mut self) {
+        self.male_values.sort_by_key(|value| value.id);
+        self.female_values.sort_by_key(|value| value.id);
+    }
+
+    /// Combines the male and female strings into a single map from each ID to
+    /// its male and female text converted to UTF-8. IDs that only appear in one
+    /// of the tables have [None] for the other, if an ID appears more than
+    /// once within the same table the last string is used
+    pub fn into_combined(self) -> HashMap<u32, (Option<String>, Option<String>)> {
+        let mut combined: HashMap<u32, (Option<String>, Option<String>)> =
+            HashMap::with_capacity(self.male_values.len().max(self.female_values.len()));
+
+        for value in self.male_values {
+            combined.entry(value.id).or_default().0 = Some(wstring_to_string(&value.value));
+        }
+
+        for value in self.female_values {
+            combined.entry(value.id).or_default().1 = Some(wstring_to_string(&value.value));
         }
+
+        combined
     }
 
-    /// Inserts a value into the tlk attempting to replace an existing one
-    pub fn insert_female_utf8(&mut self, id: u32, value: String) {
-        if self.replace_female_utf8(id, value.clone()) {
-            return;
+    /// Checks if either the male or female strings contain the provided ID
+    pub fn contains(&self, id: u32) -> bool {
+        self.male_values
+            .iter()
+            .chain(self.female_values.iter())
+            .any(|value| value.id == id)
+    }
+
+    /// Merges the strings of another tlk into this tlk, the male and female
+    /// strings are merged independently. Strings with IDs already present are
+    /// resolved using the provided strategy, when the strategy is
+    /// [MergeStrategy::Error] this tlk is left unchanged if any ID conflicts
+    pub fn merge(&mut self, other: Tlk, strategy: MergeStrategy) -> EncodeResult<()> {
+        if let MergeStrategy::Error = strategy {
+            let conflict = |existing: &[TlkString], incoming: &[TlkString]| {
+                let ids: HashSet<u32> = existing.iter().map(|value| value.id).collect();
+                incoming
+                    .iter()
+                    .find(|value| ids.contains(&value.id))
+                    .map(|value| value.id)
+            };
+
+            if let Some(id) = conflict(&self.male_values, &other.male_values)
+                .or_else(|| conflict(&self.female_values, &other.female_values))
+            {
+                return Err(EncodeError::DuplicateTlkId { id });
+            }
         }
 
-        self.female_values.push(TlkString {
-            id,
-            value: value.encode_utf16().collect(),
-        })
+        merge_strings(&mut self.male_values, other.male_values, strategy);
+        merge_strings(&mut self.female_values, other.female_values, strategy);
+        Ok(())
+    }
+}
+
+/// Strategy for resolving strings with the same ID when merging tlk
+/// files using [Tlk::merge]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The existing string is kept and the incoming string is discarded
+    KeepExisting,
+    /// The existing string is replaced by the incoming string
+    Overwrite,
+    /// Merging fails with [EncodeError::DuplicateTlkId]
+    Error,
+}
+
+/// Merges the incoming strings into the existing strings, new IDs are appended
+/// in order and existing IDs are resolved using the provided strategy
+fn merge_strings(existing: &mut Vec<TlkString>, incoming: Vec<TlkString>, strategy: MergeStrategy) {
+    // Index of each existing ID within the strings
+    let mut indexes: HashMap<u32, usize> = existing
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (value.id, index))
+        .collect();
+
+    for value in incoming {
+        match indexes.get(&value.id) {
+            Some(&index) => {
+                if let MergeStrategy::Overwrite = strategy {
+                    existing[index].value = value.value;
+                }
+            }
+            None => {
+                indexes.insert(value.id, existing.len());
+                existing.push(value);
+            }
+        }
     }
 }
 
 /// String within a tlk file
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct TlkString {
     /// ID of the value
     pub id: u32,
@@ -112,7 +339,7 @@ pub struct TlkString {
 }
 
 /// Coalesced file
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Coalesced {
     /// Coalesced version
     pub version: u32,
@@ -120,8 +347,402 @@ pub struct Coalesced {
     pub files: Vec<CoalFile>,
 }
 
+impl Coalesced {
+    /// Creates a new empty coalesced with the provided version
+    ///
+    /// ```
+    /// use me3_coalesced_parser::{
+    ///     deserialize_coalesced, serialize_coalesced, CoalFile, Coalesced, Property, Section,
+    ///     ValueType,
+    /// };
+    ///
+    /// let mut coalesced = Coalesced::new(1);
+    ///
+    /// let mut property = Property {
+    ///     name: "bindings".to_string(),
+    ///     values: Vec::new(),
+    /// };
+    /// property.push_value(ValueType::Add, Some("Jump".to_string()));
+    ///
+    /// coalesced.files.push(CoalFile {
+    ///     path: "..\\BIOGame\\Config\\BIOInput.ini".to_string(),
+    ///     sections: vec![Section {
+    ///         name: "sfxgame.sfxgamemodebase".to_string(),
+    ///         properties: vec![property],
+    ///     }],
+    /// });
+    ///
+    /// let bytes = serialize_coalesced(&coalesced).unwrap();
+    /// let parsed = deserialize_coalesced(&bytes).unwrap();
+    /// assert!(coalesced.structurally_eq(&parsed));
+    /// ```
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            files: Vec::new(),
+        }
+    }
+
+    /// Removes the file with the provided path from the coalesced returning
+    /// it if it was present. Paths are matched ignoring ASCII case
+    pub fn take_file(&mut self, path: &str) -> Option<CoalFile> {
+        let index = self
+            .files
+            .iter()
+            .position(|file| file.path.eq_ignore_ascii_case(path))?;
+        Some(self.files.remove(index))
+    }
+
+    /// Adds the provided file to the coalesced replacing any existing file
+    /// with the same path (ignoring ASCII case) in place, otherwise the file
+    /// is appended
+    pub fn put_file(&mut self, file: CoalFile) {
+        match self
+            .files
+            .iter_mut()
+            .find(|existing| existing.path.eq_ignore_ascii_case(&file.path))
+        {
+            Some(existing) => *existing = file,
+            None => self.files.push(file),
+        }
+    }
+
+    /// Gets the text of the first value of a property within the provided file
+    /// and section, see [Section::get]. File paths, section names, and
+    /// property names are all matched ignoring ASCII case
+    pub fn get(&self, file: &str, section: &str, property: &str) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|value| value.path.eq_ignore_ascii_case(file))?
+            .sections
+            .iter()
+            .find(|value| value.name.eq_ignore_ascii_case(section))?
+            .get(property)
+    }
+
+    /// Sets the property within the provided file and section to a single
+    /// [ValueType::New] value, see [Section::set]. Missing files and sections
+    /// are created, names are matched as they are by [Coalesced::get]
+    pub fn set(&mut self, file: &str, section: &str, property: &str, value: impl Into<String>) {
+        let file = match self
+            .files
+            .iter()
+            .position(|value| value.path.eq_ignore_ascii_case(file))
+        {
+            Some(index) => &mut self.files[index],
+            None => {
+                self.files.push(CoalFile {
+                    path: file.to_string(),
+                    sections: Vec::new(),
+                });
+                self.files.last_mut().expect("File was just pushed")
+            }
+        };
+
+        let section = match file
+            .sections
+            .iter()
+            .position(|value| value.name.eq_ignore_ascii_case(section))
+        {
+            Some(index) => &mut file.sections[index],
+            None => {
+                file.sections.push(Section {
+                    name: section.to_string(),
+                    properties: Vec::new(),
+                });
+                file.sections.last_mut().expect("Section was just pushed")
+            }
+        };
+
+        section.set(property, value);
+    }
+
+    /// Compares the structure of this coalesced with another coalesced
+    /// ignoring the order of files, sections and properties (Which are
+    /// matched by name). The order of values within a property is still
+    /// considered as it affects how the values are applied
+    pub fn structurally_eq(&self, other: &Coalesced) -> bool {
+        self.version == other.version
+            && unordered_eq(&self.files, &other.files, |a, b| {
+                a.path == b.path && a.structurally_eq(b)
+            })
+    }
+
+    /// Sorts the files by path, sections by name, and properties by name so
+    /// that equivalent coalesced files have the same order and therefore the
+    /// same hash. The order of values within properties is left unchanged
+    pub fn canonicalize(&mut self) {
+        self.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for file in &mut self.files {
+            file.sections.sort_by(|a, b| a.name.cmp(&b.name));
+
+            for section in &mut file.sections {
+                section.properties.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+    }
+
+    /// Checks that no two distinct string table keys share the same CRC32 hash.
+    ///
+    /// Collisions don't prevent serializing as the keys are referenced by index
+    /// however consumers that look up keys by their hash will be affected. The
+    /// serializer only runs this check when [SerializeOptions::check_collisions]
+    /// is enabled
+    ///
+    /// [SerializeOptions::check_collisions]: crate::ser::SerializeOptions::check_collisions
+    pub fn check_collisions(&self) -> EncodeResult<()> {
+        // Keys are sorted so the reported collision is consistent
+        let mut keys: Vec<&str> = self.collect_keys().into_iter().collect();
+        keys.sort_unstable();
+
+        let mut hashes: HashMap<u32, &str> = HashMap::new();
+
+        for key in keys {
+            let hash = coalesced_crc32(key.as_bytes());
+            if let Some(existing) = hashes.insert(hash, key) {
+                return Err(EncodeError::HashCollision {
+                    a: existing.to_string(),
+                    b: key.to_string(),
+                    hash,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every value is in a state that can be serialized without
+    /// losing information, [ValueType::RemoveProperty] values must not have text
+    pub fn validate(&self) -> EncodeResult<()> {
+        for file in &self.files {
+            for section in &file.sections {
+                for property in &section.properties {
+                    let invalid = property.values.iter().any(|value| {
+                        matches!(value.ty, ValueType::RemoveProperty) && value.text.is_some()
+                    });
+
+                    if invalid {
+                        return Err(EncodeError::InvalidValueState {
+                            file: file.path.clone(),
+                            section: section.name.clone(),
+                            property: property.name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every file, section, and property name fits within the
+    /// limits of the string table. Names must not be empty, their lengths must
+    /// fit in a u16, and the number of unique names must fit within the u16
+    /// string table indexes used by the index block
+    pub fn validate_names(&self) -> EncodeResult<()> {
+        let keys = self.collect_keys();
+
+        for key in &keys {
+            if key.is_empty() {
+                return Err(EncodeError::EmptyName);
+            }
+
+            if key.len() > u16::MAX as usize {
+                return Err(EncodeError::NameTooLong { length: key.len() });
+            }
+        }
+
+        if keys.len() > u16::MAX as usize + 1 {
+            return Err(EncodeError::TooManyKeys { count: keys.len() });
+        }
+
+        Ok(())
+    }
+
+    /// Lists the file path, section name, and property name of every property
+    /// that contains a [ValueType::RemoveProperty] value (i.e. the properties
+    /// that would be deleted when this coalesced is applied as a patch)
+    pub fn removals(&self) -> Vec<(String, String, String)> {
+        let mut removals = Vec::new();
+
+        for file in &self.files {
+            for section in &file.sections {
+                for property in &section.properties {
+                    if property
+                        .values
+                        .iter()
+                        .any(|value| matches!(value.ty, ValueType::RemoveProperty))
+                    {
+                        removals.push((
+                            file.path.clone(),
+                            section.name.clone(),
+                            property.name.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        removals
+    }
+
+    /// Counts the files, sections, properties, and values within the coalesced
+    pub fn stats(&self) -> CoalescedStats {
+        let mut stats = CoalescedStats {
+            file_count: self.files.len(),
+            ..Default::default()
+        };
+
+        for file in &self.files {
+            stats.section_count += file.sections.len();
+
+            for section in &file.sections {
+                stats.property_count += section.properties.len();
+
+                for property in &section.properties {
+                    stats.value_count += property.values.len();
+
+                    for value in &property.values {
+                        stats.type_counts[value.ty as usize] += 1;
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Renames every file path, section name, and property name matching
+    /// `from` (ignoring ASCII case) to `to` returning the number of names
+    /// that were replaced
+    pub fn rename_key(&mut self, from: &str, to: &str) -> usize {
+        let mut count = 0;
+
+        let mut rename = |name: &mut String| {
+            if name.eq_ignore_ascii_case(from) {
+                *name = to.to_string();
+                count += 1;
+            }
+        };
+
+        for file in &mut self.files {
+            rename(&mut file.path);
+
+            for section in &mut file.sections {
+                rename(&mut section.name);
+
+                for property in &mut section.properties {
+                    rename(&mut property.name);
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Removes duplicate values from every property, see [Property::dedup_values]
+    pub fn dedup_all_values(&mut self) {
+        self.files
+            .iter_mut()
+            .flat_map(|file| file.sections.iter_mut())
+            .flat_map(|section| section.properties.iter_mut())
+            .for_each(Property::dedup_values);
+    }
+
+    /// Reorders the values of every property so that removals come before
+    /// additions, see [Property::order_values_by_type]
+    pub fn order_values_by_type(&mut self) {
+        self.files
+            .iter_mut()
+            .flat_map(|file| file.sections.iter_mut())
+            .flat_map(|section| section.properties.iter_mut())
+            .for_each(Property::order_values_by_type);
+    }
+
+    /// Rewrites the line endings within the text of every value to use the
+    /// provided style. [ValueType::RemoveProperty] values are left untouched
+    pub fn normalize_line_endings(&mut self, style: LineEnding) {
+        let values = self
+            .files
+            .iter_mut()
+            .flat_map(|file| file.sections.iter_mut())
+            .flat_map(|section| section.properties.iter_mut())
+            .flat_map(|property| property.values.iter_mut());
+
+        for value in values {
+            if matches!(value.ty, ValueType::RemoveProperty) {
+                continue;
+            }
+
+            if let Some(text) = value.text.as_mut() {
+                // Only rewrite text that contains line endings
+                if text.contains('\n') {
+                    *text = style.apply(text);
+                }
+            }
+        }
+    }
+
+    /// Iterates every value within the coalesced along with the file,
+    /// section, and property it belongs to in the order they are stored
+    pub fn iter_values(&self) -> impl Iterator<Item = ValueRef<'_>> {
+        self.files.iter().flat_map(|file| {
+            file.sections.iter().flat_map(move |section| {
+                section.properties.iter().flat_map(move |property| {
+                    property.values.iter().map(move |value| ValueRef {
+                        file_path: &file.path,
+                        section_name: &section.name,
+                        property_name: &property.name,
+                        ty: value.ty,
+                        text: value.text.as_deref(),
+                    })
+                })
+            })
+        })
+    }
+
+    /// Collects the values matching the provided predicate along with the
+    /// file, section, and property they belong to
+    pub fn find_values<F>(&self, pred: F) -> Vec<ValueRef<'_>>
+    where
+        F: Fn(&ValueRef) -> bool,
+    {
+        self.iter_values().filter(|value| pred(value)).collect()
+    }
+
+    /// Collects the values with text matching the provided regular expression
+    /// along with the file, section, and property they belong to. An invalid
+    /// pattern is reported as the [regex::Error] from compiling it
+    #[cfg(feature = "regex")]
+    pub fn grep(&self, pattern: &str) -> Result<Vec<ValueRef<'_>>, regex::Error> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(self.find_values(|value| value.text.is_some_and(|text| regex.is_match(text))))
+    }
+
+    /// Collects the unique keys used by the files, sections, and
+    /// properties that make up the string table
+    pub(crate) fn collect_keys(&self) -> HashSet<&str> {
+        let mut keys: HashSet<&str> = HashSet::new();
+
+        for file in &self.files {
+            keys.insert(&file.path);
+
+            for section in &file.sections {
+                keys.insert(&section.name);
+
+                for property in &section.properties {
+                    keys.insert(&property.name);
+                }
+            }
+        }
+
+        keys
+    }
+}
+
 /// File within the coalesced
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct CoalFile {
     /// The relative file path
     pub path: String,
@@ -129,7 +750,74 @@ pub struct CoalFile {
     pub sections: Vec<Section>,
 }
 
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+impl CoalFile {
+    /// Compares the sections of this file with another file ignoring the
+    /// order of sections and properties
+    pub fn structurally_eq(&self, other: &CoalFile) -> bool {
+        unordered_eq(&self.sections, &other.sections, |a, b| {
+            a.name == b.name && a.structurally_eq(b)
+        })
+    }
+
+    /// Formats the file as INI-like text for display with a `[Section]` header
+    /// for each section and a `Property=Value` line for each value annotated
+    /// with the value type in a comment. This is lossy and can't be parsed back
+    ///
+    /// ```ini
+    /// [sfxgame.sfxgamemodebase]
+    /// bindings=Jump ; Add
+    /// bindings= ; RemoveProperty
+    /// ```
+    pub fn to_ini(&self) -> String {
+        let mut out = String::new();
+
+        for (index, section) in self.sections.iter().enumerate() {
+            if index > 0 {
+                out.push('\n');
+            }
+
+            // Writing to a string cannot fail so the results are ignored
+            _ = writeln!(out, "[{}]", section.name);
+
+            for property in &section.properties {
+                for value in &property.values {
+                    _ = writeln!(
+                        out,
+                        "{}={} ; {}",
+                        property.name,
+                        value.text.as_deref().unwrap_or_default(),
+                        value.ty.name()
+                    );
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Gets the path components with either separator, leading `..` and
+    /// `.` components are skipped
+    fn path_components(&self) -> impl Iterator<Item = &str> {
+        self.path
+            .split(['\\', '/'])
+            .skip_while(|component| matches!(*component, ".." | "." | ""))
+    }
+
+    /// Formats the file path for display using `/` as the separator with any
+    /// leading `..\` components removed (i.e. `BIOGame/Config/BIOGame.ini`)
+    pub fn display_path(&self) -> String {
+        self.path_components().collect::<Vec<_>>().join("/")
+    }
+
+    /// Creates a native path from the file path using the platform separator,
+    /// leading `..\` components are removed so the path is relative to the
+    /// game directory
+    pub fn native_path(&self) -> PathBuf {
+        self.path_components().collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Section {
     /// The section name
     pub name: String,
@@ -137,15 +825,135 @@ pub struct Section {
     pub properties: Vec<Property>,
 }
 
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+impl Section {
+    /// Gets the text of the first value of the property with the provided name
+    /// ignoring ASCII case, [ValueType::RemoveProperty] values are skipped as
+    /// they have no text
+    pub fn get(&self, property: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|value| value.name.eq_ignore_ascii_case(property))?
+            .values
+            .iter()
+            .find(|value| value.ty != ValueType::RemoveProperty)?
+            .text
+            .as_deref()
+    }
+
+    /// Replaces the values of the property with the provided name (Ignoring
+    /// ASCII case) with a single [ValueType::New] value, the property is created
+    /// if missing otherwise the casing of the existing name is kept
+    pub fn set(&mut self, property: &str, value: impl Into<String>) {
+        let values = vec![Value::new(ValueType::New, Some(value.into()))];
+
+        match self
+            .properties
+            .iter_mut()
+            .find(|value| value.name.eq_ignore_ascii_case(property))
+        {
+            Some(existing) => existing.values = values,
+            None => self.properties.push(Property {
+                name: property.to_string(),
+                values,
+            }),
+        }
+    }
+
+    /// Compares the properties of this section with another section
+    /// ignoring the order of the properties
+    pub fn structurally_eq(&self, other: &Section) -> bool {
+        unordered_eq(&self.properties, &other.properties, |a, b| {
+            a.name == b.name && a.structurally_eq(b)
+        })
+    }
+}
+
+/// Compares two lists ignoring their order, each item must be equal to a
+/// different item of the other list so duplicates are matched one to one
+fn unordered_eq<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut used: Vec<bool> = vec![false; b.len()];
+
+    a.iter().all(|item| {
+        let found = (0..b.len()).find(|&index| !used[index] && eq(item, &b[index]));
+
+        match found {
+            Some(index) => {
+                used[index] = true;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Property {
     /// The name of the property
     pub name: String,
-    /// The values for this property
+    /// The values for this property, values are serialized and applied in
+    /// the order they are stored so the order must be preserved
     pub values: Vec<Value>,
 }
 
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize)]
+impl Property {
+    /// Compares the values of this property with another property, the
+    /// order of the values must match
+    pub fn structurally_eq(&self, other: &Property) -> bool {
+        self.values == other.values
+    }
+
+    /// Appends a value to the end of the property values. [ValueType::RemoveProperty]
+    /// values cannot have text so the text is discarded for those values
+    pub fn push_value(&mut self, ty: ValueType, text: Option<String>) {
+        self.values.push(Value::new(ty, text))
+    }
+
+    /// Removes values with the same type and text as an earlier value, keeping
+    /// the first occurrence. Values are only compared with others of the same
+    /// [ValueType] and a value with the same text but a different type in
+    /// between (i.e. a [ValueType::Remove]) keeps later duplicates as they
+    /// have a different effect when applied
+    pub fn dedup_values(&mut self) {
+        let keep: Vec<bool> = {
+            // Type of the last kept value for each text, only a value with the
+            // same type as the last kept value with its text is a duplicate
+            let mut last_types: HashMap<Option<&str>, ValueType> = HashMap::new();
+
+            self.values
+                .iter()
+                .map(|value| {
+                    let text = value.text.as_deref();
+                    last_types.insert(text, value.ty) != Some(value.ty)
+                })
+                .collect()
+        };
+
+        let mut keep = keep.into_iter();
+        self.values.retain(|_| keep.next().unwrap_or(true));
+    }
+
+    /// Reorders the values so that [ValueType::RemoveProperty] values come
+    /// first followed by [ValueType::Remove] values and then the values that
+    /// add text. The sort is stable so values of the same kind keep their order
+    pub fn order_values_by_type(&mut self) {
+        self.values.sort_by_key(|value| match value.ty {
+            ValueType::RemoveProperty => 0,
+            ValueType::Remove => 1,
+            ValueType::New | ValueType::Add | ValueType::AddUnique => 2,
+        });
+    }
+
+    /// Iterates the values of the provided type in the order they are stored
+    pub fn values_of_type(&self, ty: ValueType) -> impl Iterator<Item = &Value> {
+        self.values.iter().filter(move |value| value.ty == ty)
+    }
+}
+
+#[derive(Debug, Hash, serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
 pub struct Value {
     /// Value type
     pub ty: ValueType,
@@ -153,7 +961,78 @@ pub struct Value {
     pub text: Option<String>,
 }
 
-#[derive(Debug, Hash, serde::Serialize, serde::Deserialize, Clone, Copy)]
+/// Counts of the contents of a coalesced
+#[derive(Debug, Clone, Default)]
+pub struct CoalescedStats {
+    /// Number of files
+    pub file_count: usize,
+    /// Number of sections across all files
+    pub section_count: usize,
+    /// Number of properties across all sections
+    pub property_count: usize,
+    /// Number of values across all properties
+    pub value_count: usize,
+    /// Number of values of each type indexed by the type value
+    type_counts: [usize; 5],
+}
+
+impl CoalescedStats {
+    /// Gets the number of values with the provided type
+    pub fn value_count_of(&self, ty: ValueType) -> usize {
+        self.type_counts[ty as usize]
+    }
+}
+
+/// Line ending style for value text
+#[derive(Debug, Clone, Copy)]
+pub enum LineEnding {
+    /// Unix style line endings (\n)
+    Lf,
+    /// Windows style line endings (\r\n)
+    CrLf,
+}
+
+impl LineEnding {
+    /// Rewrites the line endings in the provided text to use this style
+    fn apply(self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Reference to a value within a coalesced along with the
+/// names of the file, section, and property it belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct ValueRef<'a> {
+    /// Path of the file containing the value
+    pub file_path: &'a str,
+    /// Name of the section containing the value
+    pub section_name: &'a str,
+    /// Name of the property containing the value
+    pub property_name: &'a str,
+    /// Value type
+    pub ty: ValueType,
+    /// Associated text value
+    pub text: Option<&'a str>,
+}
+
+impl Value {
+    /// Creates a new value, the text of [ValueType::RemoveProperty]
+    /// values is discarded as it is never serialized
+    pub fn new(ty: ValueType, text: Option<String>) -> Self {
+        let text = match ty {
+            ValueType::RemoveProperty => None,
+            _ => text,
+        };
+
+        Self { ty, text }
+    }
+}
+
+#[derive(Debug, Hash, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ValueType {
     // Overwrite
@@ -168,6 +1047,31 @@ pub enum ValueType {
     Remove = 4,
 }
 
+impl ValueType {
+    /// Gets the name of this value type
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::New => "New",
+            Self::RemoveProperty => "RemoveProperty",
+            Self::Add => "Add",
+            Self::AddUnique => "AddUnique",
+            Self::Remove => "Remove",
+        }
+    }
+
+    /// Gets the value type from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "New" => Self::New,
+            "RemoveProperty" => Self::RemoveProperty,
+            "Add" => Self::Add,
+            "AddUnique" => Self::AddUnique,
+            "Remove" => Self::Remove,
+            _ => return None,
+        })
+    }
+}
+
 pub struct UnknownValueType;
 
 impl TryFrom<u8> for ValueType {
@@ -190,7 +1094,14 @@ impl TryFrom<u8> for ValueType {
 /// The TLK format encodes them in the opposite direction
 /// to the Coalesced file so its easier to just flip them
 /// than write separate implementations
+///
+/// Trees with one or no pairs are left unchanged as a single
+/// pair can only refer to itself
 pub(crate) fn invert_huffman_tree(pairs: &mut Vec<(i32, i32)>) {
+    if pairs.len() <= 1 {
+        return;
+    }
+
     let last_index = (pairs.len() - 1) as i32;
 
     // Reverse the pair order
@@ -207,3 +1118,29 @@ pub(crate) fn invert_huffman_tree(pairs: &mut Vec<(i32, i32)>) {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::invert_huffman_tree;
+
+    /// Tests that inverting trees with one or no pairs doesn't panic
+    /// and leaves the pairs unchanged
+    #[test]
+    fn test_invert_huffman_tree_degenerate() {
+        let mut pairs: Vec<(i32, i32)> = Vec::new();
+        invert_huffman_tree(&mut pairs);
+        assert!(pairs.is_empty());
+
+        let mut pairs: Vec<(i32, i32)> = vec![(-1, -66)];
+        invert_huffman_tree(&mut pairs);
+        assert_eq!(pairs, vec![(-1, -66)]);
+
+        // Inverting twice restores the original order
+        let original: Vec<(i32, i32)> = vec![(-1, -66), (-67, 0)];
+        let mut pairs = original.clone();
+        invert_huffman_tree(&mut pairs);
+        assert_eq!(pairs, vec![(-67, 1), (-1, -66)]);
+        invert_huffman_tree(&mut pairs);
+        assert_eq!(pairs, original);
+    }
+}
@@ -0,0 +1,25 @@
+//! Assertion helpers for checking the encoding invariants of the
+//! crate from within tests
+
+use crate::{de::deserialize_coalesced, ser::serialize_coalesced, shared::Coalesced};
+
+/// Serializes and deserializes the provided coalesced asserting that the
+/// result is structurally equal to the original
+///
+/// # Panics
+///
+/// Panics if the coalesced fails to serialize or deserialize, or if the
+/// deserialized coalesced differs from the original
+pub fn assert_coalesced_roundtrip(coalesced: &Coalesced) {
+    let bytes = serialize_coalesced(coalesced).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to deserialize coalesced");
+
+    assert_eq!(
+        parsed.version, coalesced.version,
+        "Coalesced version changed"
+    );
+    assert!(
+        parsed.structurally_eq(coalesced),
+        "Round tripped coalesced differs from the original"
+    );
+}
@@ -0,0 +1,272 @@
+//! Conversion between [Coalesced] and the XML text representation used
+//! by the coalesced editing tools
+//!
+//! ```xml
+//! <Coalesced version="1">
+//!   <CoalesceFile path="..\BIOGame\Config\BIOGame.ini">
+//!     <Section name="sfxgame.sfxgamemodebase">
+//!       <Property name="bindings">
+//!         <Value type="Add">Jump</Value>
+//!         <Value type="RemoveProperty" />
+//!       </Property>
+//!     </Section>
+//!   </CoalesceFile>
+//! </Coalesced>
+//! ```
+
+use crate::{
+    error::{DecodeError, DecodeResult},
+    shared::{CoalFile, Coalesced, Property, Section, Value, ValueType},
+};
+use quick_xml::{
+    escape::resolve_predefined_entity,
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::fmt::Write;
+
+/// Converts the provided coalesced into its XML representation
+pub fn coalesced_to_xml(coalesced: &Coalesced) -> String {
+    let mut out = String::new();
+
+    // Writing to a string cannot fail so the results are ignored
+    _ = writeln!(out, "<Coalesced version=\"{}\">", coalesced.version);
+
+    for file in &coalesced.files {
+        _ = writeln!(out, "  <CoalesceFile path=\"{}\">", escape(&file.path));
+
+        for section in &file.sections {
+            _ = writeln!(out, "    <Section name=\"{}\">", escape(&section.name));
+
+            for property in &section.properties {
+                _ = writeln!(out, "      <Property name=\"{}\">", escape(&property.name));
+
+                for value in &property.values {
+                    let ty = value.ty.name();
+                    match &value.text {
+                        Some(text) => {
+                            _ = writeln!(
+                                out,
+                                "        <Value type=\"{}\">{}</Value>",
+                                ty,
+                                escape(text)
+                            );
+                        }
+                        None => {
+                            _ = writeln!(out, "        <Value type=\"{}\" />", ty);
+                        }
+                    }
+                }
+
+                out.push_str("      </Property>\n");
+            }
+
+            out.push_str("    </Section>\n");
+        }
+
+        out.push_str("  </CoalesceFile>\n");
+    }
+
+    out.push_str("</Coalesced>\n");
+    out
+}
+
+/// Parses a coalesced from its XML representation
+pub fn coalesced_from_xml(input: &str) -> DecodeResult<Coalesced> {
+    let mut reader = Reader::from_str(input);
+
+    let mut coalesced: Option<Coalesced> = None;
+    // Current depth within the element tree
+    let mut depth: usize = 0;
+    // Text of the value currently being read
+    let mut value_text: Option<String> = None;
+
+    loop {
+        let event = reader.read_event().map_err(malformed)?;
+        match event {
+            Event::Start(start) => {
+                depth += 1;
+                if start_element(&mut coalesced, &start, depth, false)? {
+                    value_text = Some(String::new());
+                }
+            }
+            Event::Empty(start) => {
+                start_element(&mut coalesced, &start, depth + 1, true)?;
+            }
+            Event::End(_) => {
+                // Write the collected value text
+                if let Some(text) = value_text.take() {
+                    if let Some(value) = last_value(&mut coalesced) {
+                        value.text = Some(text);
+                    }
+                }
+
+                depth = depth.saturating_sub(1);
+            }
+            Event::Text(text) => {
+                let text = text.xml_content().map_err(malformed)?;
+                push_text(&mut value_text, &text)?;
+            }
+            Event::CData(data) => {
+                let text = data.decode().map_err(malformed)?;
+                push_text(&mut value_text, &text)?;
+            }
+            Event::GeneralRef(reference) => {
+                let text = match reference.resolve_char_ref().map_err(malformed)? {
+                    Some(value) => value.to_string(),
+                    None => {
+                        let name = reference.decode().map_err(malformed)?;
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| {
+                                DecodeError::MalformedXml(format!("Unknown entity: {}", name))
+                            })?
+                            .to_string()
+                    }
+                };
+                push_text(&mut value_text, &text)?;
+            }
+            Event::Eof => break,
+            // Declarations, comments and processing instructions are ignored
+            _ => {}
+        }
+    }
+
+    coalesced.ok_or_else(|| DecodeError::MalformedXml("Missing Coalesced element".to_string()))
+}
+
+/// Handles the start of an element at the provided depth, returns whether
+/// the element was a value that has text content to read
+fn start_element(
+    coalesced: &mut Option<Coalesced>,
+    start: &BytesStart,
+    depth: usize,
+    empty: bool,
+) -> DecodeResult<bool> {
+    let name = start.name();
+    let name = std::str::from_utf8(name.as_ref()).map_err(malformed)?;
+
+    let unexpected = || DecodeError::MalformedXml(format!("Unexpected element: {}", name));
+
+    match (name, depth) {
+        ("Coalesced", 1) => {
+            let version = attribute(start, "version")?
+                .parse()
+                .map_err(|_| DecodeError::MalformedXml("Invalid version".to_string()))?;
+            *coalesced = Some(Coalesced {
+                version,
+                files: Vec::new(),
+            });
+        }
+        ("CoalesceFile", 2) => {
+            let path = attribute(start, "path")?;
+            coalesced
+                .as_mut()
+                .ok_or_else(unexpected)?
+                .files
+                .push(CoalFile {
+                    path,
+                    sections: Vec::new(),
+                });
+        }
+        ("Section", 3) => {
+            let name = attribute(start, "name")?;
+            last_file(coalesced)
+                .ok_or_else(unexpected)?
+                .sections
+                .push(Section {
+                    name,
+                    properties: Vec::new(),
+                });
+        }
+        ("Property", 4) => {
+            let name = attribute(start, "name")?;
+            last_section(coalesced)
+                .ok_or_else(unexpected)?
+                .properties
+                .push(Property {
+                    name,
+                    values: Vec::new(),
+                });
+        }
+        ("Value", 5) => {
+            let ty = attribute(start, "type")?;
+            let ty = ValueType::from_name(&ty)
+                .ok_or_else(|| DecodeError::MalformedXml(format!("Unknown value type: {}", ty)))?;
+
+            // Text is assigned once the end of the value is reached
+            last_property(coalesced)
+                .ok_or_else(unexpected)?
+                .values
+                .push(Value { ty, text: None });
+
+            return Ok(!empty);
+        }
+        _ => return Err(unexpected()),
+    }
+
+    Ok(false)
+}
+
+/// Appends text to the current value, text outside of values must be whitespace
+fn push_text(value_text: &mut Option<String>, text: &str) -> DecodeResult<()> {
+    match value_text {
+        Some(value_text) => value_text.push_str(text),
+        None if text.trim().is_empty() => {}
+        None => {
+            return Err(DecodeError::MalformedXml(format!(
+                "Unexpected text: {}",
+                text
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Reads the unescaped value of a required attribute
+fn attribute(start: &BytesStart, name: &str) -> DecodeResult<String> {
+    let attribute = start
+        .try_get_attribute(name)
+        .map_err(malformed)?
+        .ok_or_else(|| DecodeError::MalformedXml(format!("Missing attribute: {}", name)))?;
+    let value = attribute.unescape_value().map_err(malformed)?;
+    Ok(value.into_owned())
+}
+
+fn last_file(coalesced: &mut Option<Coalesced>) -> Option<&mut CoalFile> {
+    coalesced.as_mut()?.files.last_mut()
+}
+
+fn last_section(coalesced: &mut Option<Coalesced>) -> Option<&mut Section> {
+    last_file(coalesced)?.sections.last_mut()
+}
+
+fn last_property(coalesced: &mut Option<Coalesced>) -> Option<&mut Property> {
+    last_section(coalesced)?.properties.last_mut()
+}
+
+fn last_value(coalesced: &mut Option<Coalesced>) -> Option<&mut Value> {
+    last_property(coalesced)?.values.last_mut()
+}
+
+/// Maps an underlying XML error into a decode error
+fn malformed<E: std::fmt::Display>(err: E) -> DecodeError {
+    DecodeError::MalformedXml(err.to_string())
+}
+
+/// Escapes text for use within XML content and attributes. Carriage returns
+/// are escaped so they aren't lost to XML line ending normalization
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\r' => out.push_str("&#13;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
@@ -1,9 +1,98 @@
 use std::{
+    borrow::Cow,
     fs::File,
     io::{Read, Write},
 };
 
-use me3_coalesced_parser::{deserialize_coalesced, serialize_coalesced};
+use me3_coalesced_parser::{
+    apply_binary_patch, coalesced_binary_patch, coalesced_crc32, coalesced_toc,
+    de::ReadBuffer,
+    decode_string, deserialize_coalesced, deserialize_coalesced_checked,
+    deserialize_coalesced_multi, deserialize_coalesced_raw, deserialize_coalesced_ref,
+    deserialize_coalesced_verbose, deserialize_coalesced_with_diagnostics,
+    deserialize_coalesced_with_options, deserialize_coalesced_with_table, deserialize_me3,
+    encode_string,
+    error::{DecodeError, EncodeError, EntryKind},
+    huffman_decode_raw, huffman_tree_to_dot, parse_header, serialize_coalesced,
+    serialize_coalesced_checked, serialize_coalesced_with_options, serialize_coalesced_with_stats,
+    serialize_coalesced_with_version, serialize_tlk, verify_coalesced, BitOrder, CoalFile,
+    Coalesced, CoalescedReader, DataBlockWriter, DecodeOptions, Encoding, Endian, HuffmanCodec,
+    KeyInterner, LineEnding, Me3File, ParseWarning, Property, Section, SerializeOptions,
+    SerializedCoalesced, StringTable, TiebreakMode, Tlk, Value, ValueRef, ValueType, ME3_MAGIC,
+};
+
+/// Creates a new value with the provided type and text
+fn value(ty: ValueType, text: Option<&str>) -> Value {
+    Value {
+        ty,
+        text: text.map(str::to_string),
+    }
+}
+
+/// Creates a new property from the provided values
+fn property(name: &str, values: Vec<Value>) -> Property {
+    Property {
+        name: name.to_string(),
+        values,
+    }
+}
+
+/// Creates a new section from the provided properties
+fn section(name: &str, properties: Vec<Property>) -> Section {
+    Section {
+        name: name.to_string(),
+        properties,
+    }
+}
+
+/// Creates a new file from the provided sections
+fn file(path: &str, sections: Vec<Section>) -> CoalFile {
+    CoalFile {
+        path: path.to_string(),
+        sections,
+    }
+}
+
+/// Creates a small coalesced for use in tests
+fn sample_coalesced() -> Coalesced {
+    Coalesced {
+        version: 1,
+        files: vec![
+            file(
+                "..\\BIOGame\\Config\\BIOGame.ini",
+                vec![
+                    section(
+                        "sfxgame.sfxgamemodebase",
+                        vec![
+                            property("bindings", vec![value(ValueType::Add, Some("Jump"))]),
+                            property("speed", vec![value(ValueType::New, Some("1.5"))]),
+                        ],
+                    ),
+                    section(
+                        "engine.engine",
+                        vec![property(
+                            "remove_me",
+                            vec![value(ValueType::RemoveProperty, None)],
+                        )],
+                    ),
+                ],
+            ),
+            file(
+                "..\\BIOGame\\Config\\BIOInput.ini",
+                vec![section(
+                    "sfxgame.sfxgamemodebase",
+                    vec![property(
+                        "bindings",
+                        vec![
+                            value(ValueType::AddUnique, Some("Crouch")),
+                            value(ValueType::Remove, Some("Jump")),
+                        ],
+                    )],
+                )],
+            ),
+        ],
+    }
+}
 
 /// Tests that a valid coalesced can be parsed, encoded, and parsed again
 /// without any errors.
@@ -24,7 +113,7 @@ fn test_coalesced_rebuild() {
     let coalesced = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
 
     // Encode
-    let bytes = serialize_coalesced(&coalesced);
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
 
     // Parse
     let coalesced = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
@@ -33,3 +122,2703 @@ fn test_coalesced_rebuild() {
     out.write_all(serde_json::to_string_pretty(&coalesced).unwrap().as_bytes())
         .unwrap();
 }
+
+/// Tests that a header declaring block sizes larger than the input
+/// is rejected before any of the blocks are read
+#[test]
+fn test_coalesced_block_size_overflow() {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&ME3_MAGIC.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes()); // Version
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // Max field name length
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // Max value length
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes()); // String table size
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // Huffman size
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // Index size
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // Data size
+    bytes.resize(100, 0);
+
+    let err = deserialize_coalesced(&bytes).expect_err("Oversized header should fail");
+    assert!(matches!(
+        err,
+        DecodeError::BlockSizeOverflow { available: 100, .. }
+    ));
+}
+
+/// Tests the coalesced CRC32 variant against known key hashes
+#[test]
+fn test_coalesced_crc32() {
+    assert_eq!(coalesced_crc32(b""), 0);
+    assert_eq!(coalesced_crc32(b"123456789"), 0xFC891918);
+    assert_eq!(
+        coalesced_crc32(b"..\\BIOGame\\Config\\BIOGame.ini"),
+        0x88388D83
+    );
+    assert_eq!(coalesced_crc32(b"sfxgame.sfxgamemodebase"), 0xA07A6B46);
+}
+
+/// Tests that structural equality ignores the order of files, sections
+/// and properties but not the contents
+#[test]
+fn test_coalesced_structurally_eq() {
+    let coalesced = sample_coalesced();
+
+    let mut reordered = sample_coalesced();
+    reordered.files.reverse();
+    reordered.files[1].sections.reverse();
+    reordered.files[1].sections[1].properties.reverse();
+
+    assert!(coalesced.structurally_eq(&reordered));
+
+    // Round trip should also be structurally equal
+    let bytes = serialize_coalesced(&reordered).expect("Failed to serialize coalesced");
+    let decoded = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+    assert!(coalesced.structurally_eq(&decoded));
+
+    let mut changed = sample_coalesced();
+    changed.files[0].sections[0].properties[1].values[0].text = Some("2.0".to_string());
+    assert!(!coalesced.structurally_eq(&changed));
+}
+
+/// Tests that structural equality matches files, sections, and properties
+/// with duplicate names one to one rather than reusing the same match
+#[test]
+fn test_coalesced_structurally_eq_duplicates() {
+    let a = property("a", vec![value(ValueType::New, Some("1"))]);
+    let b = property("b", vec![value(ValueType::New, Some("2"))]);
+
+    let properties = |properties: Vec<Property>| Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            vec![section("engine.engine", properties)],
+        )],
+    };
+
+    let left = properties(vec![a.clone(), a.clone(), b.clone()]);
+    let right = properties(vec![a.clone(), b.clone(), b.clone()]);
+    assert!(!left.structurally_eq(&right));
+    assert!(!right.structurally_eq(&left));
+
+    let reordered = properties(vec![b, a.clone(), a]);
+    assert!(left.structurally_eq(&reordered));
+
+    // Duplicate sections and files are matched the same way
+    let mut left = sample_coalesced();
+    let mut right = sample_coalesced();
+    let duplicate = left.files[0].sections[0].clone();
+    left.files[0].sections.push(duplicate);
+    let duplicate = right.files[0].sections[1].clone();
+    right.files[0].sections.push(duplicate);
+    assert!(!left.structurally_eq(&right));
+
+    let mut left = sample_coalesced();
+    let mut right = sample_coalesced();
+    left.files.push(left.files[0].clone());
+    right.files.push(right.files[1].clone());
+    assert!(!left.structurally_eq(&right));
+}
+
+/// Tests that the XML output matches the indentation and layout shown
+/// in the xml module documentation
+#[cfg(feature = "xml")]
+#[test]
+fn test_coalesced_xml_layout() {
+    use me3_coalesced_parser::coalesced_to_xml;
+
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            vec![section(
+                "sfxgame.sfxgamemodebase",
+                vec![property(
+                    "bindings",
+                    vec![
+                        value(ValueType::Add, Some("Jump")),
+                        value(ValueType::RemoveProperty, None),
+                    ],
+                )],
+            )],
+        )],
+    };
+
+    let expected = r#"<Coalesced version="1">
+  <CoalesceFile path="..\BIOGame\Config\BIOGame.ini">
+    <Section name="sfxgame.sfxgamemodebase">
+      <Property name="bindings">
+        <Value type="Add">Jump</Value>
+        <Value type="RemoveProperty" />
+      </Property>
+    </Section>
+  </CoalesceFile>
+</Coalesced>
+"#;
+    assert_eq!(coalesced_to_xml(&coalesced), expected);
+}
+
+/// Tests that a coalesced can be converted to XML and back preserving
+/// the value types and text
+#[cfg(feature = "xml")]
+#[test]
+fn test_coalesced_xml_round_trip() {
+    use me3_coalesced_parser::{coalesced_from_xml, coalesced_to_xml};
+
+    let mut coalesced = sample_coalesced();
+    coalesced.files[0].sections[0].properties[0]
+        .values
+        .push(value(ValueType::Add, Some("<Escaped & \"Quoted\">\r\n  ")));
+    coalesced.files[0].sections[0].properties[0]
+        .values
+        .push(value(ValueType::New, Some("")));
+
+    // Parse from the binary format to ensure the structure is realistic
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let coalesced = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+
+    let xml = coalesced_to_xml(&coalesced);
+    let parsed = coalesced_from_xml(&xml).expect("Failed to parse xml");
+
+    assert!(coalesced.structurally_eq(&parsed));
+    assert!(matches!(
+        parsed.files[0].sections[1].properties[0].values[0],
+        Value {
+            ty: ValueType::RemoveProperty,
+            text: None
+        }
+    ));
+}
+
+/// Tests that a coalesced can be converted to pretty JSON and back
+#[cfg(feature = "json")]
+#[test]
+fn test_coalesced_json_round_trip() {
+    use me3_coalesced_parser::{coalesced_from_json, coalesced_to_json_pretty};
+
+    let coalesced = sample_coalesced();
+    let json = coalesced_to_json_pretty(&coalesced);
+    let parsed = coalesced_from_json(&json).expect("Failed to parse json");
+
+    assert!(coalesced.structurally_eq(&parsed));
+    assert!(coalesced_from_json("{").is_err());
+}
+
+/// Tests that a coalesced can be converted to postcard bytes and back
+#[cfg(feature = "postcard")]
+#[test]
+fn test_coalesced_postcard_round_trip() {
+    use me3_coalesced_parser::{coalesced_from_postcard, coalesced_to_postcard};
+
+    let coalesced = sample_coalesced();
+    let bytes = coalesced_to_postcard(&coalesced);
+    let parsed = coalesced_from_postcard(&bytes).expect("Failed to parse postcard");
+
+    assert!(coalesced.structurally_eq(&parsed));
+    assert!(coalesced_from_postcard(&bytes[..bytes.len() / 2]).is_err());
+}
+
+/// Tests applying a patch using each of the value types
+#[test]
+fn test_coalesced_apply_patch() {
+    let base_path = "..\\BIOGame\\Config\\BIOGame.ini";
+    let mut base = Coalesced {
+        version: 1,
+        files: vec![file(
+            base_path,
+            vec![section(
+                "engine.engine",
+                vec![
+                    property("speed", vec![value(ValueType::New, Some("1.0"))]),
+                    property(
+                        "bindings",
+                        vec![
+                            value(ValueType::Add, Some("Jump")),
+                            value(ValueType::Add, Some("Crouch")),
+                        ],
+                    ),
+                    property("removed", vec![value(ValueType::New, Some("true"))]),
+                ],
+            )],
+        )],
+    };
+
+    let patch = Coalesced {
+        version: 1,
+        files: vec![
+            file(
+                base_path,
+                vec![section(
+                    "engine.engine",
+                    vec![
+                        property("speed", vec![value(ValueType::New, Some("2.0"))]),
+                        property(
+                            "bindings",
+                            vec![
+                                value(ValueType::Add, Some("Jump")),
+                                value(ValueType::AddUnique, Some("Crouch")),
+                                value(ValueType::AddUnique, Some("Sprint")),
+                                value(ValueType::Remove, Some("Jump")),
+                            ],
+                        ),
+                        property("removed", vec![value(ValueType::RemoveProperty, None)]),
+                        property("missing", vec![value(ValueType::RemoveProperty, None)]),
+                    ],
+                )],
+            ),
+            file(
+                "..\\BIOGame\\Config\\BIOInput.ini",
+                vec![section(
+                    "engine.input",
+                    vec![property("keys", vec![value(ValueType::Add, Some("W"))])],
+                )],
+            ),
+        ],
+    };
+
+    base.apply_patch(&patch);
+
+    let expected = Coalesced {
+        version: 1,
+        files: vec![
+            file(
+                base_path,
+                vec![section(
+                    "engine.engine",
+                    vec![
+                        property("speed", vec![value(ValueType::New, Some("2.0"))]),
+                        property(
+                            "bindings",
+                            vec![
+                                value(ValueType::Add, Some("Crouch")),
+                                value(ValueType::AddUnique, Some("Sprint")),
+                            ],
+                        ),
+                    ],
+                )],
+            ),
+            file(
+                "..\\BIOGame\\Config\\BIOInput.ini",
+                vec![section(
+                    "engine.input",
+                    vec![property("keys", vec![value(ValueType::Add, Some("W"))])],
+                )],
+            ),
+        ],
+    };
+
+    assert!(base.structurally_eq(&expected));
+}
+
+/// Tests that a coalesced with a large number of keys can be encoded
+/// and parsed again with every name resolved to the correct key
+#[test]
+fn test_coalesced_many_keys() {
+    let sections = (0..50)
+        .map(|section_index| {
+            let properties = (0..100)
+                .map(|property_index| {
+                    let name = format!("property_{}_{}", section_index, property_index);
+                    let text = format!("value_{}", property_index);
+                    property(&name, vec![value(ValueType::New, Some(&text))])
+                })
+                .collect();
+            section(&format!("section_{}", section_index), properties)
+        })
+        .collect();
+
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file("..\\BIOGame\\Config\\BIOGame.ini", sections)],
+    };
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+
+    assert!(coalesced.structurally_eq(&parsed));
+}
+
+/// Tests pushing values of mixed types and iterating them by type
+#[test]
+fn test_property_push_value() {
+    let mut property = property("bindings", Vec::new());
+    property.push_value(ValueType::Add, Some("Jump".to_string()));
+    property.push_value(ValueType::Remove, Some("Crouch".to_string()));
+    property.push_value(ValueType::RemoveProperty, Some("Ignored".to_string()));
+    property.push_value(ValueType::Add, Some("Sprint".to_string()));
+
+    let added: Vec<&str> = property
+        .values_of_type(ValueType::Add)
+        .filter_map(|value| value.text.as_deref())
+        .collect();
+    assert_eq!(added, vec!["Jump", "Sprint"]);
+
+    let removed: Vec<&Value> = property.values_of_type(ValueType::RemoveProperty).collect();
+    assert_eq!(removed.len(), 1);
+    assert!(removed[0].text.is_none());
+
+    // Values are stored in the order they were pushed
+    assert!(matches!(property.values[1].ty, ValueType::Remove));
+    assert!(matches!(property.values[2].ty, ValueType::RemoveProperty));
+}
+
+/// Tests that a value longer than the header max value length is still
+/// decoded fully and reported in the diagnostics rather than being truncated
+#[test]
+fn test_coalesced_value_length_exceeded() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    // Values with the exact max length must decode without diagnostics
+    let (_, diagnostics) =
+        deserialize_coalesced_with_diagnostics(&bytes, &DecodeOptions::default())
+            .expect("Failed to parse coalesced");
+    assert!(diagnostics.warnings.is_empty());
+
+    // Lower the max value length below the longest value
+    bytes[12..16].copy_from_slice(&2u32.to_le_bytes());
+
+    let (parsed, diagnostics) =
+        deserialize_coalesced_with_diagnostics(&bytes, &DecodeOptions::default())
+            .expect("Failed to parse coalesced");
+    assert!(matches!(
+        diagnostics.warnings.as_slice(),
+        [ParseWarning::MaxValueLengthExceeded { .. }, ..]
+    ));
+    assert!(parsed.structurally_eq(&coalesced));
+}
+
+/// Tests that values containing multi-byte characters are not truncated
+/// when they are the longest value
+#[test]
+fn test_coalesced_multibyte_value_length() {
+    let text = "Données de jeu 日本語";
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            vec![section(
+                "engine.engine",
+                vec![property("name", vec![value(ValueType::New, Some(text))])],
+            )],
+        )],
+    };
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+
+    assert_eq!(
+        parsed.files[0].sections[0].properties[0].values[0]
+            .text
+            .as_deref(),
+        Some(text)
+    );
+}
+
+/// Tests that the string table provided with a coalesced contains
+/// exactly the keys referenced by the coalesced with valid hashes
+#[test]
+fn test_coalesced_with_table() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let (parsed, table) =
+        deserialize_coalesced_with_table(&bytes).expect("Failed to parse coalesced");
+
+    assert!(coalesced.structurally_eq(&parsed));
+
+    let mut expected: Vec<&str> = Vec::new();
+    for file in &parsed.files {
+        expected.push(&file.path);
+        for section in &file.sections {
+            expected.push(&section.name);
+            for property in &section.properties {
+                expected.push(&property.name);
+            }
+        }
+    }
+    expected.sort();
+    expected.dedup();
+
+    let mut keys: Vec<&str> = table.iter().map(|(key, _)| key.as_str()).collect();
+    keys.sort();
+    assert_eq!(keys, expected);
+
+    for (key, hash) in &table {
+        assert_eq!(coalesced_crc32(key.as_bytes()), *hash);
+    }
+}
+
+/// Tests that distinct keys sharing the same CRC32 hash are detected
+#[test]
+fn test_coalesced_hash_collision() {
+    let mut coalesced = sample_coalesced();
+    assert!(coalesced.check_collisions().is_ok());
+
+    // Both keys hash to 0x78009F81
+    coalesced.files[0].sections[0].properties.extend([
+        property("key_14591828", vec![value(ValueType::New, Some("a"))]),
+        property("key_40040200", vec![value(ValueType::New, Some("b"))]),
+    ]);
+
+    let err = coalesced
+        .check_collisions()
+        .expect_err("Colliding keys should fail");
+    match err {
+        EncodeError::HashCollision { a, b, hash } => {
+            assert_eq!(a, "key_14591828");
+            assert_eq!(b, "key_40040200");
+            assert_eq!(hash, 0x78009F81);
+        }
+        err => panic!("Unexpected error: {}", err),
+    }
+}
+
+/// Tests that colliding keys are only rejected when serializing with
+/// collision checks enabled
+#[test]
+fn test_coalesced_serialize_check_collisions() {
+    let mut coalesced = sample_coalesced();
+
+    // Both keys hash to 0x78009F81
+    coalesced.files[0].sections[0].properties.extend([
+        property("key_14591828", vec![value(ValueType::New, Some("a"))]),
+        property("key_40040200", vec![value(ValueType::New, Some("b"))]),
+    ]);
+
+    assert!(serialize_coalesced(&coalesced).is_ok());
+
+    let options = SerializeOptions {
+        check_collisions: true,
+        ..Default::default()
+    };
+    let err = serialize_coalesced_with_options(&coalesced, &options)
+        .expect_err("Colliding keys should fail");
+    assert!(matches!(
+        err,
+        EncodeError::HashCollision {
+            hash: 0x78009F81,
+            ..
+        }
+    ));
+}
+
+/// Tests that trailing padding bytes are allowed by default and
+/// rejected when strict trailing is enabled
+#[test]
+fn test_coalesced_trailing_bytes() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+    bytes.extend_from_slice(&[0; 8]);
+
+    let coalesced = deserialize_coalesced(&bytes).expect("Padded coalesced should parse");
+    assert!(coalesced.structurally_eq(&sample_coalesced()));
+
+    let options = DecodeOptions {
+        strict_trailing: true,
+        ..Default::default()
+    };
+    let err = deserialize_coalesced_with_options(&bytes, &options)
+        .expect_err("Strict trailing should fail");
+    assert!(matches!(err, DecodeError::TrailingBytes { count: 8 }));
+}
+
+/// Tests that canonicalizing differently ordered but equivalent
+/// coalesced files produces the same hash
+#[test]
+fn test_coalesced_canonicalize() {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    fn hash(coalesced: &Coalesced) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        coalesced.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut coalesced = sample_coalesced();
+    let mut reordered = sample_coalesced();
+    reordered.files.reverse();
+    reordered.files[1].sections.reverse();
+    reordered.files[1].sections[1].properties.reverse();
+
+    assert_ne!(hash(&coalesced), hash(&reordered));
+
+    coalesced.canonicalize();
+    reordered.canonicalize();
+
+    assert_eq!(hash(&coalesced), hash(&reordered));
+}
+
+/// Tests rendering a small huffman tree as a DOT graph
+#[test]
+fn test_huffman_tree_to_dot() {
+    let pairs = [(-1 - 'a' as i32, -1 - 'b' as i32), (0, -1), (5, -1)];
+    let dot = huffman_tree_to_dot(&pairs[..2]);
+
+    assert!(dot.starts_with("digraph huffman {"));
+    assert!(dot.contains("n1 [label=\"root\"];"));
+    assert!(dot.contains("n1 -> n0 [label=\"0\"];"));
+    assert!(dot.contains("n1 -> l1_1 [label=\"1\"];"));
+    assert!(dot.contains("l1_1 [shape=box, label=\"'\\\\0'\"];"));
+    assert!(dot.contains("l0_0 [shape=box, label=\"'a'\"];"));
+    assert!(dot.contains("l0_1 [shape=box, label=\"'b'\"];"));
+
+    // Links outside the tree are highlighted
+    let dot = huffman_tree_to_dot(&pairs);
+    assert!(dot.contains("n5 [color=red, label=\"missing 5\"];"));
+}
+
+/// Tests that incrementally serializing an unchanged coalesced produces
+/// the same bytes and that changes are still written
+#[test]
+fn test_coalesced_serialize_incremental() {
+    let mut coalesced = sample_coalesced();
+    let full = SerializedCoalesced::new(&coalesced).expect("Failed to serialize coalesced");
+
+    let incremental = coalesced
+        .serialize_incremental(&full)
+        .expect("Failed to serialize coalesced");
+    assert_eq!(incremental.as_bytes(), full.as_bytes());
+
+    // Reordering values keeps the keys and characters but changes the data
+    coalesced.files[1].sections[0].properties[0]
+        .values
+        .reverse();
+    let incremental = coalesced
+        .serialize_incremental(&full)
+        .expect("Failed to serialize coalesced");
+    assert_ne!(incremental.as_bytes(), full.as_bytes());
+
+    let parsed = deserialize_coalesced(incremental.as_bytes()).expect("Failed to parse coalesced");
+    assert!(coalesced.structurally_eq(&parsed));
+}
+
+/// Tests that serializing a medium sized coalesced writes exactly the
+/// declared block sizes and serializes the same bytes when repeated
+#[test]
+fn test_coalesced_serialize_medium() {
+    let files = (0..4)
+        .map(|file_index| {
+            let sections = (0..20)
+                .map(|section_index| {
+                    let properties = (0..25)
+                        .map(|property_index| {
+                            let name = format!("property_{}", property_index);
+                            let values = (0..3)
+                                .map(|value_index| {
+                                    let text = format!(
+                                        "value_{}_{}_{}",
+                                        file_index, section_index, value_index
+                                    );
+                                    value(ValueType::Add, Some(&text))
+                                })
+                                .collect();
+                            property(&name, values)
+                        })
+                        .collect();
+                    section(&format!("section_{}", section_index), properties)
+                })
+                .collect();
+            file(
+                &format!("..\\BIOGame\\Config\\File{}.ini", file_index),
+                sections,
+            )
+        })
+        .collect();
+
+    let coalesced = Coalesced { version: 1, files };
+
+    let serialized = SerializedCoalesced::new(&coalesced).expect("Failed to serialize coalesced");
+    let bytes = serialized.as_bytes();
+
+    // Header block sizes followed by the total bits field
+    let block_sizes: usize = (16..32)
+        .step_by(4)
+        .map(|offset| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize)
+        .sum();
+    assert_eq!(bytes.len(), 32 + block_sizes + 4);
+
+    let repeated = coalesced
+        .serialize_incremental(&serialized)
+        .expect("Failed to serialize coalesced");
+    assert_eq!(repeated.as_bytes(), bytes);
+
+    let parsed = deserialize_coalesced(bytes).expect("Failed to parse coalesced");
+    assert!(coalesced.structurally_eq(&parsed));
+}
+
+/// Tests that creating a remove property value discards its text
+#[test]
+fn test_value_new() {
+    let removed = Value::new(ValueType::RemoveProperty, Some("Ignored".to_string()));
+    assert!(removed.text.is_none());
+
+    let added = Value::new(ValueType::Add, Some("Jump".to_string()));
+    assert_eq!(added.text.as_deref(), Some("Jump"));
+}
+
+/// Tests that remove property values with text fail validation
+#[test]
+fn test_coalesced_validate() {
+    let mut coalesced = sample_coalesced();
+    coalesced
+        .validate()
+        .expect("Sample coalesced should be valid");
+
+    coalesced.files[0].sections[1].properties[0].values[0].text = Some("Lost".to_string());
+
+    let err = coalesced
+        .validate()
+        .expect_err("Text on remove property should fail");
+    assert!(matches!(
+        err,
+        EncodeError::InvalidValueState { ref section, ref property, .. }
+            if section == "engine.engine" && property == "remove_me"
+    ));
+}
+
+/// Tests that the keys of a borrowed coalesced point into the input buffer
+#[test]
+fn test_coalesced_ref_borrowed_keys() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let borrowed = deserialize_coalesced_ref(&bytes).expect("Failed to parse coalesced");
+
+    let range = bytes.as_ptr_range();
+    let file = &borrowed.files[0];
+    let section = &file.sections[0];
+    let property = &section.properties[0];
+
+    for key in [&file.path, &section.name, &property.name] {
+        assert!(matches!(key, Cow::Borrowed(_)));
+        assert!(range.contains(&key.as_ptr()));
+    }
+
+    assert!(coalesced.structurally_eq(&borrowed.into_owned()));
+}
+
+/// Tests that the header can be parsed without the rest of the file
+#[test]
+fn test_coalesced_parse_header() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let header = parse_header(&bytes[..32]).expect("Failed to parse header");
+    assert_eq!(header.version, coalesced.version);
+    assert_eq!(header.string_table_offset, 32);
+    assert_eq!(header.total_size(), bytes.len());
+
+    let err = parse_header(&bytes[..16]).expect_err("Truncated header should fail");
+    assert!(matches!(err, DecodeError::UnexpectedEof { .. }));
+}
+
+/// Creates a coalesced with only new values as produced by applying patches
+fn applied_coalesced() -> Coalesced {
+    Coalesced {
+        version: 1,
+        files: vec![
+            file(
+                "..\\BIOGame\\Config\\BIOGame.ini",
+                vec![
+                    section(
+                        "sfxgame.sfxgamemodebase",
+                        vec![
+                            property(
+                                "bindings",
+                                vec![
+                                    value(ValueType::New, Some("Jump")),
+                                    value(ValueType::New, Some("Crouch")),
+                                ],
+                            ),
+                            property("speed", vec![value(ValueType::New, Some("1.5"))]),
+                        ],
+                    ),
+                    section(
+                        "engine.engine",
+                        vec![property("unused", vec![value(ValueType::New, Some("1"))])],
+                    ),
+                ],
+            ),
+            file(
+                "..\\BIOGame\\Config\\BIOInput.ini",
+                vec![section(
+                    "sfxgame.sfxgamemodebase",
+                    vec![property(
+                        "sensitivity",
+                        vec![value(ValueType::New, Some("2"))],
+                    )],
+                )],
+            ),
+        ],
+    }
+}
+
+/// Tests that applying the diff of two coalesced files to the base
+/// produces the edited coalesced
+#[test]
+fn test_coalesced_diff() {
+    let base = applied_coalesced();
+
+    let mut edited = applied_coalesced();
+    {
+        let game = &mut edited.files[0];
+        let mode = &mut game.sections[0];
+        // Changed value
+        mode.properties[0].values[1].text = Some("Sprint".to_string());
+        // Removed property
+        mode.properties.remove(1);
+        // Added property
+        mode.properties
+            .push(property("health", vec![value(ValueType::New, Some("100"))]));
+        // Removed section
+        game.sections.remove(1);
+    }
+    // Removed file
+    edited.files.remove(1);
+    // Added file
+    edited.files.push(file(
+        "..\\BIOGame\\Config\\BIOWeapon.ini",
+        vec![section(
+            "sfxgame.sfxweapon",
+            vec![property("damage", vec![value(ValueType::New, Some("50"))])],
+        )],
+    ));
+
+    let patch = Coalesced::diff(&base, &edited);
+
+    // Only the changed file sections are included in the patch
+    let names: Vec<&str> = patch.files[0].sections[0]
+        .properties
+        .iter()
+        .map(|property| property.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["bindings", "health", "speed"]);
+
+    let mut patched = applied_coalesced();
+    patched.apply_patch(&patch);
+    assert!(patched.structurally_eq(&edited));
+
+    // Diffing identical files produces an empty patch
+    assert!(Coalesced::diff(&base, &applied_coalesced())
+        .files
+        .is_empty());
+}
+
+/// Tests that a value offset pointing outside the index block is
+/// reported rather than reading unrelated bytes
+#[test]
+fn test_coalesced_invalid_value_offset() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+
+    let read_u32 = |bytes: &[u8], offset: usize| -> usize {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+    };
+
+    // Index block follows the header, string table, and huffman tree
+    let index_start = 32 + read_u32(&bytes, 16) + read_u32(&bytes, 20);
+
+    // Follow the first file and first section to the first property offset
+    let file_offset = read_u32(&bytes, index_start + 4);
+    let section_offset = read_u32(&bytes, index_start + file_offset + 4);
+    let value_offset_pos = index_start + file_offset + section_offset + 4;
+
+    bytes[value_offset_pos..value_offset_pos + 4].copy_from_slice(&0x00FF_FFFFu32.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Oversized offset should fail");
+    assert!(matches!(err, DecodeError::InvalidOffset { .. }));
+}
+
+/// Tests that iterating the values visits every value in order along
+/// with the names of the containers they belong to
+#[test]
+fn test_coalesced_iter_values() {
+    let coalesced = sample_coalesced();
+
+    let values: Vec<(&str, &str, &str, &str, Option<&str>)> = coalesced
+        .iter_values()
+        .map(|value| {
+            (
+                value.file_path,
+                value.section_name,
+                value.property_name,
+                value.ty.name(),
+                value.text,
+            )
+        })
+        .collect();
+
+    let game = "..\\BIOGame\\Config\\BIOGame.ini";
+    let input = "..\\BIOGame\\Config\\BIOInput.ini";
+    let mode = "sfxgame.sfxgamemodebase";
+
+    assert_eq!(
+        values,
+        vec![
+            (game, mode, "bindings", "Add", Some("Jump")),
+            (game, mode, "speed", "New", Some("1.5")),
+            (game, "engine.engine", "remove_me", "RemoveProperty", None),
+            (input, mode, "bindings", "AddUnique", Some("Crouch")),
+            (input, mode, "bindings", "Remove", Some("Jump")),
+        ]
+    );
+}
+
+/// Tests that keys sharing the same hash are ordered according
+/// to the tiebreak mode
+#[test]
+fn test_coalesced_key_tiebreak() {
+    // Both keys share the hash 0x78009F81
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "key_40040200",
+            vec![section(
+                "key_14591828",
+                vec![property(
+                    "bindings",
+                    vec![value(ValueType::New, Some("Jump"))],
+                )],
+            )],
+        )],
+    };
+
+    let tied_keys = |mode: TiebreakMode| -> Vec<String> {
+        let options = SerializeOptions {
+            key_tiebreak: mode,
+            ..Default::default()
+        };
+        let bytes = serialize_coalesced_with_options(&coalesced, &options)
+            .expect("Failed to serialize coalesced");
+        let (_, table) =
+            deserialize_coalesced_with_table(&bytes).expect("Failed to parse coalesced");
+        table
+            .into_iter()
+            .filter(|(_, hash)| *hash == 0x78009F81)
+            .map(|(key, _)| key)
+            .collect()
+    };
+
+    assert_eq!(
+        tied_keys(TiebreakMode::CrcThenInsertionOrder),
+        vec!["key_40040200", "key_14591828"]
+    );
+    assert_eq!(
+        tied_keys(TiebreakMode::CrcThenLexicographic),
+        vec!["key_14591828", "key_40040200"]
+    );
+}
+
+/// Tests that verifying a valid coalesced passes and that a single
+/// corrupted byte within the string table is detected
+#[test]
+fn test_verify_coalesced() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+    verify_coalesced(&bytes).expect("Valid coalesced should verify");
+
+    // Corrupt the last byte of the last string table key
+    let string_table_size = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+    bytes[32 + string_table_size - 1] ^= 0x20;
+
+    let err = verify_coalesced(&bytes).expect_err("Corrupted coalesced should fail");
+    assert!(matches!(err, DecodeError::StringTableHashMismatch));
+}
+
+/// Tests that a coalesced without any files can be serialized and parsed
+#[test]
+fn test_coalesced_empty() {
+    let coalesced = Coalesced::new(1);
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse empty coalesced");
+
+    assert_eq!(parsed.version, 1);
+    assert!(parsed.files.is_empty());
+
+    // Values that are all empty only use the null terminator
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            vec![section(
+                "engine.engine",
+                vec![property("empty", vec![value(ValueType::New, Some(""))])],
+            )],
+        )],
+    };
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse empty values");
+    assert!(coalesced.structurally_eq(&parsed));
+}
+
+/// Tests taking a file out of a coalesced, changing it, and putting it back
+#[test]
+fn test_coalesced_take_put_file() {
+    let mut coalesced = sample_coalesced();
+    let path = "..\\BIOGame\\Config\\BIOInput.ini";
+
+    let mut input = coalesced.take_file(path).expect("Missing input file");
+    assert_eq!(coalesced.files.len(), 1);
+    assert!(coalesced.take_file(path).is_none());
+
+    input.sections[0].properties[0].values[0].text = Some("Sprint".to_string());
+    coalesced.put_file(input);
+
+    let values: Vec<Option<&str>> = coalesced
+        .iter_values()
+        .filter(|value| value.file_path == path)
+        .map(|value| value.text)
+        .collect();
+    assert_eq!(values, vec![Some("Sprint"), Some("Jump")]);
+
+    // Putting a file with an existing path replaces it
+    let replacement = file(path, vec![section("sfxgame.sfxgamemodebase", Vec::new())]);
+    coalesced.put_file(replacement);
+
+    let paths: Vec<&str> = coalesced
+        .files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect();
+    assert_eq!(paths, vec!["..\\BIOGame\\Config\\BIOGame.ini", path]);
+    assert!(coalesced.files[1].sections[0].properties.is_empty());
+}
+
+/// Tests that the estimated size matches the serialized length
+#[test]
+fn test_coalesced_estimated_size() {
+    let mut coalesced = sample_coalesced();
+    assert_eq!(
+        coalesced.estimated_size(),
+        serialize_coalesced(&coalesced)
+            .expect("Failed to serialize coalesced")
+            .len()
+    );
+
+    // Remove property text isn't written so it doesn't affect the size
+    coalesced.files[0].sections[1].properties[0].values[0].text = Some("Ignored".to_string());
+    assert_eq!(
+        coalesced.estimated_size(),
+        serialize_coalesced(&coalesced)
+            .expect("Failed to serialize coalesced")
+            .len()
+    );
+
+    let applied = applied_coalesced();
+    assert_eq!(
+        applied.estimated_size(),
+        serialize_coalesced(&applied)
+            .expect("Failed to serialize coalesced")
+            .len()
+    );
+
+    let empty = Coalesced::new(1);
+    assert_eq!(
+        empty.estimated_size(),
+        serialize_coalesced(&empty)
+            .expect("Failed to serialize coalesced")
+            .len()
+    );
+}
+
+/// Tests that the version can be overridden when serializing
+#[test]
+fn test_coalesced_serialize_with_version() {
+    let coalesced = sample_coalesced();
+
+    let bytes =
+        serialize_coalesced_with_version(&coalesced, 2).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+
+    assert_eq!(parsed.version, 2);
+    assert_eq!(coalesced.version, 1);
+}
+
+/// Tests that mixed line endings are normalized to the requested style
+#[test]
+fn test_coalesced_normalize_line_endings() {
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![
+                property("text", vec![value(ValueType::New, Some("a\r\nb\nc\r\n"))]),
+                property("remove_me", vec![value(ValueType::RemoveProperty, None)]),
+            ],
+        )],
+    ));
+
+    let text = |coalesced: &Coalesced| {
+        coalesced
+            .iter_values()
+            .map(|value| value.text.map(str::to_string))
+            .collect::<Vec<_>>()
+    };
+
+    coalesced.normalize_line_endings(LineEnding::Lf);
+    assert_eq!(text(&coalesced), vec![Some("a\nb\nc\n".to_string()), None]);
+
+    coalesced.normalize_line_endings(LineEnding::CrLf);
+    assert_eq!(
+        text(&coalesced),
+        vec![Some("a\r\nb\r\nc\r\n".to_string()), None]
+    );
+}
+
+/// Tests that a huge section count over a small index block fails
+/// cleanly rather than allocating for every declared section
+#[test]
+fn test_coalesced_huge_section_count() {
+    // Single file without any sections so its section count ends the index block
+    let mut coalesced = Coalesced::new(1);
+    coalesced
+        .files
+        .push(file("..\\BIOGame\\Config\\BIOGame.ini", Vec::new()));
+
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let read_u32 = |bytes: &[u8], offset: usize| -> usize {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+    };
+
+    let index_start = 32 + read_u32(&bytes, 16) + read_u32(&bytes, 20);
+    let file_offset = read_u32(&bytes, index_start + 4);
+    let sections_count_pos = index_start + file_offset;
+
+    bytes[sections_count_pos..sections_count_pos + 2].copy_from_slice(&u16::MAX.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Huge section count should fail");
+    assert!(matches!(err, DecodeError::UnexpectedEof { .. }));
+}
+
+/// Tests that lazily reading a single property matches the values
+/// from a full parse
+#[test]
+fn test_coalesced_reader_value() {
+    let bytes = serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+    let reader = CoalescedReader::new(&bytes).expect("Failed to read coalesced");
+
+    let path = "..\\BIOGame\\Config\\BIOInput.ini";
+    let section = "sfxgame.sfxgamemodebase";
+
+    let lazy = reader
+        .value(path, section, "bindings")
+        .expect("Failed to decode values")
+        .expect("Missing property");
+
+    let eager = parsed
+        .iter_values()
+        .filter(|value| {
+            value.file_path == path
+                && value.section_name == section
+                && value.property_name == "bindings"
+        })
+        .map(|value| (value.ty.name(), value.text));
+
+    assert!(lazy
+        .iter()
+        .map(|value| (value.ty.name(), value.text.as_deref()))
+        .eq(eager));
+
+    assert!(reader
+        .value(path, section, "missing")
+        .expect("Failed to read index")
+        .is_none());
+    assert!(reader
+        .value("missing", section, "bindings")
+        .expect("Failed to read index")
+        .is_none());
+}
+
+/// Tests that lazily reading a property matches names ignoring ASCII
+/// case the same as [Coalesced::get]
+#[test]
+fn test_coalesced_reader_value_case_insensitive() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let reader = CoalescedReader::new(&bytes).expect("Failed to read coalesced");
+
+    let path = "..\\BIOGAME\\CONFIG\\BIOINPUT.INI";
+    let section = "SFXGame.SFXGameModeBase";
+
+    let values = reader
+        .value(path, section, "Bindings")
+        .expect("Failed to decode values")
+        .expect("Missing property");
+
+    assert_eq!(
+        values[0].text.as_deref(),
+        coalesced.get(path, section, "Bindings")
+    );
+}
+
+/// Tests that applying a diff writes changed values as [ValueType::New] with
+/// the edited text and keeps properties that were changed to be empty
+#[test]
+fn test_coalesced_diff_value_types() {
+    let base = sample_coalesced();
+
+    let mut edited = base.clone();
+    let properties = &mut edited.files[0].sections[0].properties;
+    properties[0].values = vec![
+        value(ValueType::Add, Some("Jump")),
+        value(ValueType::AddUnique, Some("Crouch")),
+        value(ValueType::New, Some("Run")),
+    ];
+    properties[1].values.clear();
+    properties.push(property(
+        "aliases",
+        vec![value(ValueType::AddUnique, Some("Sprint"))],
+    ));
+
+    let patch = Coalesced::diff(&base, &edited);
+    assert!(patch
+        .iter_values()
+        .all(|value| matches!(value.ty, ValueType::New | ValueType::RemoveProperty)));
+
+    let mut applied = base.clone();
+    applied.apply_patch(&patch);
+
+    let properties = &applied.files[0].sections[0].properties;
+    assert_eq!(
+        properties[0],
+        property(
+            "bindings",
+            vec![
+                value(ValueType::New, Some("Jump")),
+                value(ValueType::New, Some("Crouch")),
+                value(ValueType::New, Some("Run")),
+            ]
+        )
+    );
+    assert_eq!(properties[1], property("speed", vec![]));
+    assert_eq!(
+        properties[2],
+        property("aliases", vec![value(ValueType::New, Some("Sprint"))])
+    );
+}
+
+/// Tests that a property renamed with only a change in case is included
+/// in the diff and that applying it restores the edited casing
+#[test]
+fn test_coalesced_diff_case_rename() {
+    let base = sample_coalesced();
+    let mut edited = base.clone();
+    edited.rename_key("speed", "Speed");
+
+    let patch = Coalesced::diff(&base, &edited);
+    assert!(!patch.files.is_empty());
+
+    let mut applied = base.clone();
+    applied.apply_patch(&patch);
+    assert!(applied.structurally_eq(&edited));
+}
+
+/// Tests that repetitive value text is compressed and that the
+/// reported block sizes match the header
+#[test]
+fn test_coalesced_serialize_with_stats() {
+    let properties = (0..20)
+        .map(|index| {
+            property(
+                &format!("property_{}", index),
+                vec![value(ValueType::New, Some("aaaaaaaaaabbbbbbbbbb"))],
+            )
+        })
+        .collect();
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section("engine.engine", properties)],
+    ));
+
+    let (bytes, stats) =
+        serialize_coalesced_with_stats(&coalesced).expect("Failed to serialize coalesced");
+
+    assert_eq!(stats.raw_text_bytes, 20 * 20);
+    assert!(stats.compressed_data_bytes <= stats.raw_text_bytes);
+    // File path, section name, and property names
+    assert_eq!(stats.key_count, 22);
+
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    assert_eq!(stats.string_table_bytes, header.string_table_size as usize);
+    assert_eq!(stats.index_bytes, header.index_size as usize);
+    assert_eq!(stats.compressed_data_bytes, header.data_size as usize);
+}
+
+/// Tests that a value reaching the max value length without a null
+/// terminator is only accepted when terminators aren't required
+#[test]
+fn test_coalesced_require_null_terminator() {
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![property(
+                "value",
+                vec![value(ValueType::New, Some("aaaaaaaa"))],
+            )],
+        )],
+    ));
+
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    // With only two symbols each has a single bit code so the first data byte
+    // holds the eight characters and the null terminator is in the second byte
+    assert_eq!(u32::from_le_bytes(bytes[28..32].try_into().unwrap()), 2);
+    bytes[28..32].copy_from_slice(&1u32.to_le_bytes());
+
+    // The total bits must also fit within the shortened data block
+    let total_bits_start = bytes.len() - 2 - 4;
+    bytes[total_bits_start..total_bits_start + 4].copy_from_slice(&8u32.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Missing terminator should fail");
+    assert!(matches!(err, DecodeError::MissingNullTerminator));
+
+    let options = DecodeOptions {
+        require_null_terminator: false,
+        ..Default::default()
+    };
+    let parsed = deserialize_coalesced_with_options(&bytes, &options)
+        .expect("Missing terminator should be accepted");
+    assert!(coalesced.structurally_eq(&parsed));
+}
+
+/// Tests that a key containing a Windows-1252 byte only validates
+/// when decoding keys as Windows-1252
+#[test]
+fn test_coalesced_windows_1252_keys() {
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![property("It's", vec![value(ValueType::New, Some("1"))])],
+        )],
+    ));
+
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let read_u32 = |bytes: &[u8], offset: usize| -> usize {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+    };
+
+    // Replace the apostrophe with a Windows-1252 right single quote
+    let key_pos = bytes
+        .windows(4)
+        .position(|window| window == b"It's")
+        .expect("Missing key");
+    bytes[key_pos + 2] = 0x92;
+
+    // Update the hash of the key within the string table
+    let hash = coalesced_crc32(&bytes[key_pos..key_pos + 4]);
+    let count = read_u32(&bytes, 36);
+    let entry = (0..count)
+        .map(|index| 40 + index * 8)
+        .find(|entry| 32 + 8 + read_u32(&bytes, entry + 4) + 2 == key_pos)
+        .expect("Missing key entry");
+    bytes[entry..entry + 4].copy_from_slice(&hash.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Lossy UTF-8 key should fail");
+    assert!(matches!(err, DecodeError::StringTableHashMismatch));
+
+    let options = DecodeOptions {
+        encoding: Encoding::Windows1252,
+        ..Default::default()
+    };
+    let parsed =
+        deserialize_coalesced_with_options(&bytes, &options).expect("Failed to parse coalesced");
+    assert_eq!(
+        parsed.files[0].sections[0].properties[0].name,
+        "It\u{2019}s"
+    );
+}
+
+/// Tests that renaming a key replaces every file, section, and property
+/// name using it
+#[test]
+fn test_coalesced_rename_key() {
+    let mut coalesced = sample_coalesced();
+
+    assert_eq!(coalesced.rename_key("bindings", "keybinds"), 2);
+    assert_eq!(coalesced.rename_key("bindings", "keybinds"), 0);
+
+    let names: Vec<&str> = coalesced
+        .iter_values()
+        .map(|value| value.property_name)
+        .collect();
+    assert_eq!(
+        names,
+        vec!["keybinds", "speed", "remove_me", "keybinds", "keybinds"]
+    );
+
+    // Renamed keys are written to the string table
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let (_, table) = deserialize_coalesced_with_table(&bytes).expect("Failed to parse coalesced");
+    assert!(table.iter().any(|(key, _)| key == "keybinds"));
+    assert!(!table.iter().any(|(key, _)| key == "bindings"));
+}
+
+/// Tests counting the contents of a coalesced
+#[test]
+fn test_coalesced_stats() {
+    let stats = sample_coalesced().stats();
+
+    assert_eq!(stats.file_count, 2);
+    assert_eq!(stats.section_count, 3);
+    assert_eq!(stats.property_count, 4);
+    assert_eq!(stats.value_count, 5);
+
+    assert_eq!(stats.value_count_of(ValueType::New), 1);
+    assert_eq!(stats.value_count_of(ValueType::RemoveProperty), 1);
+    assert_eq!(stats.value_count_of(ValueType::Add), 1);
+    assert_eq!(stats.value_count_of(ValueType::AddUnique), 1);
+    assert_eq!(stats.value_count_of(ValueType::Remove), 1);
+}
+
+/// Tests that a value bit offset too large for the offset bits of the index
+/// entry is an error rather than overwriting the type bits, and that a
+/// non-standard type/offset split round trips when decoded with the same split
+#[test]
+fn test_coalesced_offset_too_large() {
+    let coalesced = sample_coalesced();
+
+    // Only 3 bits remain for the offset so the second value can't fit
+    let options = SerializeOptions {
+        value_type_bits: 29,
+        ..Default::default()
+    };
+    let err =
+        serialize_coalesced_with_options(&coalesced, &options).expect_err("Offset should not fit");
+    assert!(matches!(err, EncodeError::OffsetTooLarge { offset } if offset > 0b111));
+
+    let options = SerializeOptions {
+        value_type_bits: 4,
+        ..Default::default()
+    };
+    let bytes = serialize_coalesced_with_options(&coalesced, &options)
+        .expect("Failed to serialize coalesced");
+
+    let decode_options = DecodeOptions {
+        value_type_bits: 4,
+        ..Default::default()
+    };
+    let decoded = deserialize_coalesced_with_options(&bytes, &decode_options)
+        .expect("Failed to parse coalesced");
+    assert!(decoded.structurally_eq(&coalesced));
+}
+
+/// Tests that serializing the same coalesced twice produces byte-identical
+/// output when many characters share the same frequency
+#[test]
+fn test_coalesced_serialize_deterministic() {
+    let mut coalesced = sample_coalesced();
+    coalesced.files[0].sections[0].properties[0]
+        .values
+        .push(value(
+            ValueType::Add,
+            Some("abcdefghijklmnopqrstuvwxyz0123456789"),
+        ));
+
+    let first = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    for _ in 0..8 {
+        let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+        assert_eq!(bytes, first);
+    }
+}
+
+/// Tests validating the file, section, and property names of a coalesced
+#[test]
+fn test_coalesced_validate_names() {
+    let mut coalesced = sample_coalesced();
+    coalesced
+        .validate_names()
+        .expect("Sample names should be valid");
+
+    coalesced.files[0].sections[0].name = "a".repeat(u16::MAX as usize + 1);
+    let err = coalesced
+        .validate_names()
+        .expect_err("Name should be too long");
+    assert!(matches!(err, EncodeError::NameTooLong { length: 65536 }));
+
+    coalesced.files[0].sections[0].name = "a".repeat(u16::MAX as usize);
+    coalesced
+        .validate_names()
+        .expect("Max length name should be valid");
+
+    coalesced.files[1].path = String::new();
+    let err = coalesced
+        .validate_names()
+        .expect_err("Name should be empty");
+    assert!(matches!(err, EncodeError::EmptyName));
+}
+
+/// Tests that a string table key with the wrong hash is only an error
+/// when hash verification is enabled
+#[test]
+fn test_coalesced_skip_hash_verification() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    // Corrupt the hash of the first string table entry
+    bytes[32 + 8] ^= 0xFF;
+
+    let err = deserialize_coalesced(&bytes).expect_err("Wrong hash should fail");
+    assert!(matches!(err, DecodeError::StringTableHashMismatch));
+
+    let options = DecodeOptions {
+        verify_hashes: false,
+        ..Default::default()
+    };
+    let parsed =
+        deserialize_coalesced_with_options(&bytes, &options).expect("Failed to parse coalesced");
+    assert!(parsed.structurally_eq(&coalesced));
+}
+
+/// Tests that duplicate values of the same type collapse into the first
+/// occurrence while distinct values and types remain
+#[test]
+fn test_coalesced_dedup_values() {
+    let mut coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOInput.ini",
+            vec![section(
+                "sfxgame.sfxgamemodebase",
+                vec![property(
+                    "bindings",
+                    vec![
+                        value(ValueType::Add, Some("Jump")),
+                        value(ValueType::Add, Some("Crouch")),
+                        value(ValueType::Add, Some("Jump")),
+                        value(ValueType::AddUnique, Some("Jump")),
+                        value(ValueType::Add, Some("Crouch")),
+                        value(ValueType::Remove, Some("Crouch")),
+                        value(ValueType::Add, Some("Crouch")),
+                    ],
+                )],
+            )],
+        )],
+    };
+
+    coalesced.dedup_all_values();
+
+    let values: Vec<(&str, Option<&str>)> = coalesced
+        .iter_values()
+        .map(|value| (value.ty.name(), value.text))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            ("Add", Some("Jump")),
+            ("Add", Some("Crouch")),
+            ("AddUnique", Some("Jump")),
+            ("Remove", Some("Crouch")),
+            ("Add", Some("Crouch")),
+        ]
+    );
+}
+
+/// Tests that the raw blocks of a coalesced match the blocks written when
+/// the fully parsed coalesced is serialized again
+#[test]
+fn test_coalesced_raw() {
+    let bytes = serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to parse raw coalesced");
+
+    assert_eq!(
+        raw.string_table.len(),
+        raw.header.string_table_size as usize
+    );
+    assert_eq!(raw.index_block.len(), raw.header.index_size as usize);
+    assert_eq!(raw.data_block.len(), raw.header.data_size as usize);
+    assert_eq!(raw.data_block.len(), (raw.total_bits as usize).div_ceil(8));
+
+    let parsed = deserialize_coalesced(&bytes).expect("Failed to parse coalesced");
+    let reserialized = serialize_coalesced(&parsed).expect("Failed to serialize coalesced");
+    let reparsed = deserialize_coalesced_raw(&reserialized).expect("Failed to parse raw coalesced");
+
+    assert_eq!(raw.string_table, reparsed.string_table);
+    assert_eq!(raw.huffman_tree, reparsed.huffman_tree);
+    assert_eq!(raw.index_block, reparsed.index_block);
+    assert_eq!(raw.total_bits, reparsed.total_bits);
+    assert_eq!(raw.data_block, reparsed.data_block);
+}
+
+/// Tests formatting coalesced file paths for display and as native paths
+#[test]
+fn test_coalesced_file_display_path() {
+    let backslash = file("..\\BIOGame\\Config\\BIOGame.ini", vec![]);
+    assert_eq!(backslash.display_path(), "BIOGame/Config/BIOGame.ini");
+    assert_eq!(
+        backslash.native_path(),
+        ["BIOGame", "Config", "BIOGame.ini"]
+            .iter()
+            .collect::<std::path::PathBuf>()
+    );
+
+    let mixed = file("../..\\BIOGame/Config\\BIOInput.ini", vec![]);
+    assert_eq!(mixed.display_path(), "BIOGame/Config/BIOInput.ini");
+
+    // The original path is untouched
+    assert_eq!(backslash.path, "..\\BIOGame\\Config\\BIOGame.ini");
+}
+
+/// Tests the public round trip assertion with a synthetic coalesced
+#[cfg(feature = "testutil")]
+#[test]
+fn test_coalesced_testutil_roundtrip() {
+    use me3_coalesced_parser::testutil::assert_coalesced_roundtrip;
+
+    assert_coalesced_roundtrip(&sample_coalesced());
+    assert_coalesced_roundtrip(&applied_coalesced());
+    assert_coalesced_roundtrip(&Coalesced::new(2));
+}
+
+/// Tests that only the properties containing remove property values
+/// are listed as removals
+#[test]
+fn test_coalesced_removals() {
+    let mut coalesced = sample_coalesced();
+    coalesced.files[1].sections[0].properties[0]
+        .values
+        .push(value(ValueType::RemoveProperty, None));
+
+    assert_eq!(
+        coalesced.removals(),
+        vec![
+            (
+                "..\\BIOGame\\Config\\BIOGame.ini".to_string(),
+                "engine.engine".to_string(),
+                "remove_me".to_string()
+            ),
+            (
+                "..\\BIOGame\\Config\\BIOInput.ini".to_string(),
+                "sfxgame.sfxgamemodebase".to_string(),
+                "bindings".to_string()
+            ),
+        ]
+    );
+
+    assert!(applied_coalesced().removals().is_empty());
+}
+
+/// Tests that an index offset larger than the offset fields allow is an
+/// error rather than being truncated, using a reduced limit
+#[test]
+fn test_coalesced_index_too_large() {
+    let coalesced = sample_coalesced();
+
+    let options = SerializeOptions {
+        max_index_offset: 16,
+        ..Default::default()
+    };
+    let err = serialize_coalesced_with_options(&coalesced, &options)
+        .expect_err("Index offset should not fit");
+    assert!(matches!(err, EncodeError::IndexTooLarge { offset } if offset > 16));
+}
+
+/// Tests that coalesced and tlk files are detected from their magic
+#[test]
+fn test_deserialize_me3() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    match deserialize_me3(&bytes).expect("Failed to parse coalesced") {
+        Me3File::Coalesced(parsed) => assert!(parsed.structurally_eq(&coalesced)),
+        Me3File::Tlk(_) => panic!("Coalesced was detected as a tlk"),
+    }
+
+    let mut tlk = Tlk::new(3, 2);
+    tlk.insert_male_utf8(1, "Shepard".to_string());
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+    match deserialize_me3(&bytes).expect("Failed to parse tlk") {
+        Me3File::Tlk(parsed) => assert_eq!(parsed.get_male_utf8(1).as_deref(), Some("Shepard")),
+        Me3File::Coalesced(_) => panic!("Tlk was detected as a coalesced"),
+    }
+
+    let err = deserialize_me3(&[0u8; 32]).expect_err("Unknown magic should fail");
+    assert!(matches!(err, DecodeError::UnknownFileMagic));
+}
+
+/// Tests finding values using a predicate
+#[test]
+fn test_coalesced_find_values() {
+    let coalesced = sample_coalesced();
+
+    let found: Vec<(&str, Option<&str>)> = coalesced
+        .find_values(|value| value.property_name == "bindings")
+        .iter()
+        .map(|value| (value.file_path, value.text))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("..\\BIOGame\\Config\\BIOGame.ini", Some("Jump")),
+            ("..\\BIOGame\\Config\\BIOInput.ini", Some("Crouch")),
+            ("..\\BIOGame\\Config\\BIOInput.ini", Some("Jump")),
+        ]
+    );
+
+    assert!(coalesced.find_values(|_| false).is_empty());
+}
+
+/// Tests searching value text using a regular expression
+#[cfg(feature = "regex")]
+#[test]
+fn test_coalesced_grep() {
+    let coalesced = sample_coalesced();
+
+    let found: Vec<Option<&str>> = coalesced
+        .grep("^(Jump|[0-9.]+)$")
+        .expect("Pattern should be valid")
+        .iter()
+        .map(|value| value.text)
+        .collect();
+    assert_eq!(found, vec![Some("Jump"), Some("1.5"), Some("Jump")]);
+
+    let err = coalesced.grep("(").expect_err("Pattern should be invalid");
+    assert!(matches!(err, regex::Error::Syntax(_)));
+}
+
+/// Tests that the data block bit offsets of the values start at zero and
+/// increase with each value
+#[test]
+fn test_coalesced_value_offsets() {
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOInput.ini",
+            vec![section(
+                "sfxgame.sfxgamemodebase",
+                vec![property(
+                    "bindings",
+                    vec![
+                        value(ValueType::Add, Some("Jump")),
+                        value(ValueType::Add, Some("Crouch")),
+                        value(ValueType::AddUnique, Some("Sprint")),
+                    ],
+                )],
+            )],
+        )],
+    };
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let reader = CoalescedReader::new(&bytes).expect("Failed to read coalesced");
+
+    let values = reader
+        .value_with_offsets(
+            "..\\BIOGame\\Config\\BIOInput.ini",
+            "sfxgame.sfxgamemodebase",
+            "bindings",
+        )
+        .expect("Failed to decode values")
+        .expect("Missing property");
+
+    let texts: Vec<Option<&str>> = values
+        .iter()
+        .map(|(value, _)| value.text.as_deref())
+        .collect();
+    assert_eq!(texts, vec![Some("Jump"), Some("Crouch"), Some("Sprint")]);
+
+    let offsets: Vec<u32> = values.iter().map(|(_, offset)| *offset).collect();
+    assert_eq!(offsets[0], 0);
+    assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+/// Tests formatting a coalesced file as INI text
+#[test]
+fn test_coalesced_file_to_ini() {
+    let coalesced = sample_coalesced();
+
+    let ini = coalesced.files[0].to_ini();
+    assert_eq!(
+        ini,
+        "[sfxgame.sfxgamemodebase]\n\
+         bindings=Jump ; Add\n\
+         speed=1.5 ; New\n\
+         \n\
+         [engine.engine]\n\
+         remove_me= ; RemoveProperty\n"
+    );
+
+    let ini = coalesced.files[1].to_ini();
+    assert!(ini.contains("[sfxgame.sfxgamemodebase]\n"));
+    assert!(ini.contains("bindings=Crouch ; AddUnique\n"));
+    assert!(ini.contains("bindings=Jump ; Remove\n"));
+}
+
+/// Tests that a max decode length from the options is enforced even
+/// though the header max value length allows longer values
+#[test]
+fn test_coalesced_max_total_decoded() {
+    let bytes = serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+
+    let options = DecodeOptions {
+        max_total_decoded: Some(3),
+        ..Default::default()
+    };
+    let err =
+        deserialize_coalesced_with_options(&bytes, &options).expect_err("Long value should fail");
+    assert!(matches!(
+        err,
+        DecodeError::ValueLengthExceeded { max_length: 3 }
+    ));
+
+    // A cap larger than the longest value is fine
+    let options = DecodeOptions {
+        max_total_decoded: Some(6),
+        ..Default::default()
+    };
+    deserialize_coalesced_with_options(&bytes, &options).expect("Failed to parse coalesced");
+}
+
+/// Tests that mutating a cloned coalesced leaves the original unchanged
+#[test]
+fn test_coalesced_clone() {
+    let original = sample_coalesced();
+    let mut cloned = original.clone();
+
+    cloned.files[0].sections[0].properties[0].values[0].text = Some("Crouch".to_string());
+    cloned.files.pop();
+
+    assert_eq!(original.files.len(), 2);
+    assert_eq!(
+        original.files[0].sections[0].properties[0].values[0]
+            .text
+            .as_deref(),
+        Some("Jump")
+    );
+    assert!(original.structurally_eq(&sample_coalesced()));
+    assert!(!original.structurally_eq(&cloned));
+}
+
+/// Tests comparing values, value types, and whole coalesced files directly
+#[test]
+fn test_coalesced_equality() {
+    assert_eq!(
+        value(ValueType::Add, Some("Jump")),
+        Value::new(ValueType::Add, Some("Jump".to_string()))
+    );
+    assert_ne!(
+        value(ValueType::Add, Some("Jump")),
+        value(ValueType::AddUnique, Some("Jump"))
+    );
+    assert_ne!(ValueType::Add, ValueType::AddUnique);
+    assert_eq!(ValueType::RemoveProperty, ValueType::RemoveProperty);
+
+    assert_eq!(sample_coalesced(), sample_coalesced());
+    assert_ne!(sample_coalesced(), applied_coalesced());
+}
+
+/// Tests decoding a data block that stores its bits most significant bit
+/// first by reversing the bits of each byte of a least significant first file
+#[test]
+fn test_coalesced_msb0_bit_order() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    let data_start = bytes.len() - header.data_size as usize;
+    bytes[data_start..]
+        .iter_mut()
+        .for_each(|byte| *byte = byte.reverse_bits());
+
+    let options = DecodeOptions {
+        bit_order: BitOrder::Msb0,
+        ..Default::default()
+    };
+    let parsed =
+        deserialize_coalesced_with_options(&bytes, &options).expect("Failed to parse coalesced");
+    assert_eq!(parsed, coalesced);
+}
+
+/// Tests that unknown value type bits are an error by default and are read
+/// as new values when enabled in the options
+#[test]
+fn test_coalesced_unknown_type_as_new() {
+    let coalesced = Coalesced {
+        version: 1,
+        files: vec![file(
+            "..\\BIOGame\\Config\\BIOInput.ini",
+            vec![section(
+                "sfxgame.sfxgamemodebase",
+                vec![property(
+                    "bindings",
+                    vec![value(ValueType::Add, Some("Jump"))],
+                )],
+            )],
+        )],
+    };
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    // Replace the add type of the only value entry (Offset 0) with type 7
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    let index_start = 32 + header.string_table_size as usize + header.huffman_size as usize;
+    let index_end = index_start + header.index_size as usize;
+    let entry = bytes[index_start..index_end]
+        .windows(4)
+        .position(|window| window == 0x4000_0000u32.to_le_bytes())
+        .expect("Missing value entry")
+        + index_start;
+    bytes[entry..entry + 4].copy_from_slice(&0xE000_0000u32.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Unknown type should fail");
+    assert!(matches!(err, DecodeError::UnknownValueType));
+
+    let options = DecodeOptions {
+        unknown_type_as_new: true,
+        ..Default::default()
+    };
+    let parsed =
+        deserialize_coalesced_with_options(&bytes, &options).expect("Failed to parse coalesced");
+    let values: Vec<ValueRef> = parsed.iter_values().collect();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].ty, ValueType::New);
+    assert_eq!(values[0].text, Some("Jump"));
+}
+
+/// Tests that the string table keys are listed in the order they are
+/// written to the string table when serializing
+#[test]
+fn test_coalesced_string_table_keys() {
+    let coalesced = sample_coalesced();
+    let keys = coalesced.string_table_keys();
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let (_, table) = deserialize_coalesced_with_table(&bytes).expect("Failed to parse coalesced");
+    let table: Vec<String> = table.into_iter().map(|(key, _)| key).collect();
+
+    assert_eq!(keys, table);
+    assert_eq!(keys.len(), 7);
+}
+
+/// Tests that a total bits count larger than the data block is reported
+/// up front rather than decoding past the end of the data
+#[test]
+fn test_coalesced_data_bits_overflow() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+
+    // The total bits count directly precedes the data block
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    let total_bits_start = bytes.len() - header.data_size as usize - 4;
+    bytes[total_bits_start..total_bits_start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let err = deserialize_coalesced(&bytes).expect_err("Inflated total bits should fail");
+    assert!(matches!(
+        err,
+        DecodeError::DataBitsOverflow {
+            total_bits: u32::MAX,
+            ..
+        }
+    ));
+}
+
+/// Tests that the offsets of a computed string table point to the length
+/// prefixed bytes of their keys and that it matches the serialized table
+#[test]
+fn test_coalesced_compute_string_table() {
+    let coalesced = sample_coalesced();
+    let table: StringTable = coalesced.compute_string_table();
+    let bytes = table.to_bytes();
+
+    assert_eq!(table.entries().len(), coalesced.string_table_keys().len());
+
+    for entry in table.entries() {
+        let start = 8 + entry.offset as usize;
+        let length = u16::from_le_bytes([bytes[start], bytes[start + 1]]) as usize;
+        assert_eq!(&bytes[start + 2..start + 2 + length], entry.key.as_bytes());
+    }
+
+    let serialized = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let header = parse_header(&serialized).expect("Failed to parse header");
+    assert_eq!(
+        &serialized[32..32 + header.string_table_size as usize],
+        bytes.as_slice()
+    );
+}
+
+/// Tests that a coalesced serialized big-endian (As used by the console
+/// versions) differs from little-endian and decodes back to the original
+#[test]
+fn test_coalesced_big_endian() {
+    let coalesced = sample_coalesced();
+
+    let little = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let big = serialize_coalesced_with_options(
+        &coalesced,
+        &SerializeOptions {
+            endian: Endian::Big,
+            ..Default::default()
+        },
+    )
+    .expect("Failed to serialize coalesced");
+
+    assert_eq!(little.len(), big.len());
+    assert_ne!(little, big);
+    assert_eq!(&big[0..4], &ME3_MAGIC.to_be_bytes());
+
+    let options = DecodeOptions {
+        endian: Endian::Big,
+        ..Default::default()
+    };
+    let decoded =
+        deserialize_coalesced_with_options(&big, &options).expect("Failed to deserialize");
+    assert_eq!(decoded, coalesced);
+
+    // Reading with the wrong byte order fails on the magic
+    assert!(matches!(
+        deserialize_coalesced(&big),
+        Err(DecodeError::UnknownFileMagic)
+    ));
+}
+
+/// Tests that the data block writer packs codes least significant bit
+/// first and matches the data block written when serializing
+#[test]
+fn test_coalesced_data_block_writer() {
+    let mut writer = DataBlockWriter::new();
+    writer.push_code(&[true, false, true]);
+    writer.push_code(&[true; 5]);
+    writer.push_code(&[false, true]);
+    assert_eq!(writer.len_bits(), 10);
+    assert_eq!(writer.finish(), vec![0b1111_1101, 0b0000_0010]);
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![property(
+                "value",
+                vec![value(ValueType::New, Some("aaaaaaaa"))],
+            )],
+        )],
+    ));
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to read raw coalesced");
+
+    // With only two symbols the root pair holds both leaves
+    let (left, _) = raw.huffman_tree[raw.huffman_tree.len() - 1];
+    let a_code = left != -1 - 'a' as i32;
+
+    let mut writer = DataBlockWriter::new();
+    for _ in 0..8 {
+        writer.push_code(&[a_code]);
+    }
+    writer.push_code(&[!a_code]);
+
+    assert_eq!(writer.len_bits(), raw.total_bits as usize);
+    assert_eq!(writer.finish(), raw.data_block);
+}
+
+/// Tests that a string table key not referenced by the index is reported
+/// as unused while well formed files have no unused keys
+#[test]
+fn test_coalesced_unused_keys() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let reader = CoalescedReader::new(&bytes).expect("Failed to create reader");
+    assert!(reader
+        .unused_keys()
+        .expect("Failed to find keys")
+        .is_empty());
+
+    // Append an orphan key to the end of the table so the indexes of the
+    // existing keys are unchanged
+    let mut keys = coalesced.string_table_keys();
+    keys.push("orphan.key".to_string());
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let string_table = StringTable::new(&keys).to_bytes();
+
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    let mut injected = bytes[..32].to_vec();
+    injected[16..20].copy_from_slice(&(string_table.len() as u32).to_le_bytes());
+    injected.extend_from_slice(&string_table);
+    injected.extend_from_slice(&bytes[32 + header.string_table_size as usize..]);
+
+    let reader = CoalescedReader::new(&injected).expect("Failed to create reader");
+    assert_eq!(
+        reader.unused_keys().expect("Failed to find keys"),
+        vec!["orphan.key".to_string()]
+    );
+}
+
+/// Tests that ordering values by type moves removals before additions
+/// while keeping the order and text of values of the same kind
+#[test]
+fn test_coalesced_order_values_by_type() {
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "sfxgame.sfxgamemodebase",
+            vec![property(
+                "bindings",
+                vec![
+                    value(ValueType::Add, Some("Jump")),
+                    value(ValueType::Remove, Some("Crouch")),
+                    value(ValueType::AddUnique, Some("Sprint")),
+                    value(ValueType::RemoveProperty, None),
+                    value(ValueType::New, Some("Cover")),
+                    value(ValueType::Remove, Some("Reload")),
+                ],
+            )],
+        )],
+    ));
+
+    coalesced.order_values_by_type();
+
+    assert_eq!(
+        coalesced.files[0].sections[0].properties[0].values,
+        vec![
+            value(ValueType::RemoveProperty, None),
+            value(ValueType::Remove, Some("Crouch")),
+            value(ValueType::Remove, Some("Reload")),
+            value(ValueType::Add, Some("Jump")),
+            value(ValueType::AddUnique, Some("Sprint")),
+            value(ValueType::New, Some("Cover")),
+        ]
+    );
+}
+
+/// Tests that a key with invalid UTF-8 is decoded lossily with a warning
+/// and that trailing bytes are reported rather than silently ignored
+#[test]
+fn test_coalesced_verbose_warnings() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let (decoded, warnings) = deserialize_coalesced_verbose(&bytes).expect("Failed to decode");
+    assert_eq!(decoded, coalesced);
+    assert!(warnings.is_empty());
+
+    // Replace the first byte of a key with an invalid UTF-8 byte and update
+    // its hash to match the lossy decoded key
+    let table = coalesced.compute_string_table();
+    let (index, entry) = table
+        .entries()
+        .iter()
+        .enumerate()
+        .find(|(_, entry)| entry.key == "bindings")
+        .expect("Missing key");
+
+    let key_offset = 32 + 8 + entry.offset as usize + 2;
+    bytes[key_offset] = 0xFF;
+    let hash = coalesced_crc32("\u{FFFD}indings".as_bytes());
+    let hash_offset = 32 + 8 + index * 8;
+    bytes[hash_offset..hash_offset + 4].copy_from_slice(&hash.to_le_bytes());
+
+    let end = bytes.len();
+    bytes.extend_from_slice(&[0; 3]);
+
+    let (decoded, warnings) = deserialize_coalesced_verbose(&bytes).expect("Failed to decode");
+    assert_eq!(
+        warnings,
+        vec![
+            ParseWarning::TrailingBytes {
+                offset: end,
+                count: 3
+            },
+            ParseWarning::LossyKey { offset: key_offset },
+        ]
+    );
+    assert!(decoded.files[0].sections[0]
+        .properties
+        .iter()
+        .any(|property| property.name == "\u{FFFD}indings"));
+}
+
+/// Tests that the checked envelope round trips and that a flipped payload
+/// byte or a truncated payload is detected before parsing
+#[test]
+fn test_coalesced_checked() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced_checked(&coalesced).expect("Failed to serialize");
+
+    let decoded = deserialize_coalesced_checked(&bytes).expect("Failed to deserialize");
+    assert_eq!(decoded, coalesced);
+
+    // The envelope is not a plain coalesced file
+    assert!(matches!(
+        deserialize_coalesced(&bytes),
+        Err(DecodeError::UnknownFileMagic)
+    ));
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(
+        deserialize_coalesced_checked(truncated),
+        Err(DecodeError::UnexpectedEof { .. })
+    ));
+
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    assert!(matches!(
+        deserialize_coalesced_checked(&bytes),
+        Err(DecodeError::ChecksumMismatch { .. })
+    ));
+}
+
+/// Tests that interned keys are reindexed by their CRC32 hash once the
+/// interner is finalized and that interning is idempotent
+#[test]
+fn test_coalesced_key_interner() {
+    let keys = [
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "sfxgame.sfxgamemodebase",
+        "bindings",
+        "engine.engine",
+        "value",
+    ];
+
+    let mut interner = KeyInterner::new();
+    for (index, key) in keys.iter().enumerate() {
+        assert_eq!(interner.intern(key), index as u32);
+    }
+    assert_eq!(interner.intern("bindings"), 2);
+    assert!(!interner.is_finalized());
+
+    interner.finalize();
+    assert!(interner.is_finalized());
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by_key(|key| coalesced_crc32(key.as_bytes()));
+    assert_eq!(interner.keys(), sorted.as_slice());
+
+    for (index, key) in sorted.iter().enumerate() {
+        assert_eq!(interner.index(key), Some(index as u32));
+        assert_eq!(interner.intern(key), index as u32);
+    }
+
+    let table = interner.string_table();
+    let table_keys: Vec<&str> = table
+        .entries()
+        .iter()
+        .map(|entry| entry.key.as_str())
+        .collect();
+    assert_eq!(table_keys, sorted);
+}
+
+/// Tests that concatenated coalesced files are each deserialized and
+/// that a partial file at the end is an error
+#[test]
+fn test_coalesced_multi() {
+    let first = sample_coalesced();
+    let second = applied_coalesced();
+
+    let mut bytes = serialize_coalesced(&first).expect("Failed to serialize coalesced");
+    let second_bytes = serialize_coalesced(&second).expect("Failed to serialize coalesced");
+    bytes.extend_from_slice(&second_bytes);
+
+    let files = deserialize_coalesced_multi(&bytes).expect("Failed to deserialize");
+    assert_eq!(files, vec![first, second]);
+
+    let partial = &bytes[..bytes.len() - 1];
+    assert!(matches!(
+        deserialize_coalesced_multi(partial),
+        Err(DecodeError::BlockSizeOverflow { .. })
+    ));
+
+    assert!(deserialize_coalesced_multi(&[])
+        .expect("Empty input has no files")
+        .is_empty());
+}
+
+/// Tests reading the first value of a property and that missing
+/// properties and removed properties have no value
+#[test]
+fn test_coalesced_get() {
+    let coalesced = sample_coalesced();
+
+    assert_eq!(
+        coalesced.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "sfxgame.sfxgamemodebase",
+            "speed"
+        ),
+        Some("1.5")
+    );
+    assert_eq!(coalesced.files[0].sections[0].get("bindings"), Some("Jump"));
+
+    assert_eq!(
+        coalesced.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "sfxgame.sfxgamemodebase",
+            "missing"
+        ),
+        None
+    );
+    assert_eq!(
+        coalesced.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "engine.engine",
+            "remove_me"
+        ),
+        None
+    );
+    assert_eq!(
+        coalesced.get("missing.ini", "engine.engine", "remove_me"),
+        None
+    );
+}
+
+/// Tests that setting a property creates the missing file, section and
+/// property and that setting an existing property replaces its values
+#[test]
+fn test_coalesced_set() {
+    let mut coalesced = sample_coalesced();
+
+    coalesced.set(
+        "..\\BIOGame\\Config\\BIOAI.ini",
+        "sfxgame.sfxai",
+        "aggression",
+        "high",
+    );
+    assert_eq!(
+        coalesced.files.last(),
+        Some(&file(
+            "..\\BIOGame\\Config\\BIOAI.ini",
+            vec![section(
+                "sfxgame.sfxai",
+                vec![property(
+                    "aggression",
+                    vec![value(ValueType::New, Some("high"))]
+                )],
+            )],
+        ))
+    );
+
+    let property_count = coalesced.files[0].sections[0].properties.len();
+    coalesced.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "sfxgame.sfxgamemodebase",
+        "bindings",
+        String::from("Crouch"),
+    );
+    assert_eq!(
+        coalesced.files[0].sections[0].properties[0],
+        property("bindings", vec![value(ValueType::New, Some("Crouch"))])
+    );
+    assert_eq!(
+        coalesced.files[0].sections[0].properties.len(),
+        property_count
+    );
+    assert_eq!(
+        coalesced.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "sfxgame.sfxgamemodebase",
+            "bindings"
+        ),
+        Some("Crouch")
+    );
+}
+
+/// Tests that decoding the raw symbols of a value provides the code
+/// points of its characters without the null terminator
+#[test]
+fn test_coalesced_huffman_decode_raw() {
+    let mut coalesced = Coalesced::new(1);
+    coalesced.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "engine.engine",
+        "value",
+        "Sh\u{E9}p\u{1F680}",
+    );
+
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let reader = CoalescedReader::new(&bytes).expect("Failed to create reader");
+    let values = reader
+        .value_with_offsets("..\\BIOGame\\Config\\BIOGame.ini", "engine.engine", "value")
+        .expect("Failed to read value")
+        .expect("Missing value");
+    let (_, offset) = values[0];
+
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to read raw coalesced");
+    let symbols = huffman_decode_raw(
+        raw.data_block,
+        &raw.huffman_tree,
+        offset as usize,
+        usize::MAX,
+        true,
+        BitOrder::Lsb0,
+    )
+    .expect("Failed to decode symbols");
+
+    assert_eq!(symbols, vec![0x53, 0x68, 0xE9, 0x70, 0x1F680]);
+}
+
+/// Tests that the table of contents lists the file paths without
+/// decoding any values (A corrupt huffman tree is never used)
+#[test]
+fn test_coalesced_toc() {
+    let coalesced = sample_coalesced();
+    let mut bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let paths: Vec<String> = coalesced
+        .files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect();
+    assert_eq!(coalesced_toc(&bytes).expect("Failed to read toc"), paths);
+
+    // Empty the huffman tree so that decoding any value fails
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    let huffman_start = 32 + header.string_table_size as usize;
+    bytes[huffman_start..huffman_start + 2].copy_from_slice(&0u16.to_le_bytes());
+
+    assert!(deserialize_coalesced(&bytes).is_err());
+    assert_eq!(coalesced_toc(&bytes).expect("Failed to read toc"), paths);
+}
+
+/// Tests that a failed speculative read restores the cursor while a
+/// successful read keeps its progress
+#[test]
+fn test_read_buffer_with_restore() {
+    let bytes = [1, 0, 0, 0, 2, 0];
+    let mut reader = ReadBuffer::new(&bytes);
+
+    // Reading two u32 values fails part way through the second
+    let result = reader.with_restore(|reader| {
+        let first = reader.read_u32()?;
+        let second = reader.read_u32()?;
+        Ok((first, second))
+    });
+    assert!(matches!(result, Err(DecodeError::UnexpectedEof { .. })));
+    assert_eq!(reader.position(), 0);
+
+    let value = reader
+        .with_restore(|reader| reader.read_u32())
+        .expect("Failed to read u32");
+    assert_eq!(value, 1);
+    assert_eq!(reader.position(), 4);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 2);
+}
+
+/// Tests that empty text round trips as an empty string rather than
+/// [None] while [ValueType::RemoveProperty] values write no data
+#[test]
+fn test_coalesced_empty_text() {
+    let coalesced_with = |values: Vec<Value>| {
+        let mut coalesced = Coalesced::new(1);
+        coalesced.files.push(file(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            vec![section("engine.engine", vec![property("value", values)])],
+        ));
+        coalesced
+    };
+
+    let coalesced = coalesced_with(vec![
+        value(ValueType::New, Some("")),
+        value(ValueType::Add, Some("")),
+        value(ValueType::RemoveProperty, None),
+        value(ValueType::Add, Some("Jump")),
+    ]);
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let decoded = deserialize_coalesced(&bytes).expect("Failed to deserialize coalesced");
+    assert_eq!(decoded, coalesced);
+
+    // Missing text on a value that isn't a removal is written as an empty
+    // string rather than decoding the text of the next value
+    let missing = coalesced_with(vec![
+        value(ValueType::New, None),
+        value(ValueType::Add, Some("Jump")),
+    ]);
+    let bytes = serialize_coalesced(&missing).expect("Failed to serialize coalesced");
+    let decoded = deserialize_coalesced(&bytes).expect("Failed to deserialize coalesced");
+    assert_eq!(
+        decoded.files[0].sections[0].properties[0].values,
+        vec![
+            value(ValueType::New, Some("")),
+            value(ValueType::Add, Some("Jump")),
+        ]
+    );
+
+    let removal = coalesced_with(vec![value(ValueType::RemoveProperty, None)]);
+    let bytes = serialize_coalesced(&removal).expect("Failed to serialize coalesced");
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to read raw coalesced");
+    assert_eq!(raw.total_bits, 0);
+
+    let empty = coalesced_with(vec![value(ValueType::New, Some(""))]);
+    let bytes = serialize_coalesced(&empty).expect("Failed to serialize coalesced");
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to read raw coalesced");
+    assert!(raw.total_bits > 0);
+}
+
+/// Tests that applying a binary patch to the base produces the
+/// structure of the edited coalesced
+#[test]
+fn test_coalesced_binary_patch() {
+    let base = sample_coalesced();
+
+    let mut edited = base.clone();
+    edited.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "sfxgame.sfxgamemodebase",
+        "speed",
+        "2.0",
+    );
+    edited.files[0].sections.remove(1);
+    edited.set(
+        "..\\BIOGame\\Config\\BIOAI.ini",
+        "sfxgame.sfxai",
+        "aggression",
+        "high",
+    );
+
+    let base_bytes = serialize_coalesced(&base).expect("Failed to serialize coalesced");
+    let edited_bytes = serialize_coalesced(&edited).expect("Failed to serialize coalesced");
+
+    let patch = coalesced_binary_patch(&base_bytes, &edited_bytes).expect("Failed to create patch");
+    assert!(patch.len() < edited_bytes.len());
+
+    let patched = apply_binary_patch(&base_bytes, &patch).expect("Failed to apply patch");
+    let patched = deserialize_coalesced(&patched).expect("Failed to deserialize coalesced");
+    assert!(patched.structurally_eq(&edited));
+}
+
+/// Tests that a binary patch between coalesced files with different
+/// versions produces the edited version when applied
+#[test]
+fn test_coalesced_binary_patch_version() {
+    let base = sample_coalesced();
+
+    let mut edited = base.clone();
+    edited.version = 2;
+    edited.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "sfxgame.sfxgamemodebase",
+        "speed",
+        "2.0",
+    );
+
+    let base_bytes = serialize_coalesced(&base).expect("Failed to serialize coalesced");
+    let edited_bytes = serialize_coalesced(&edited).expect("Failed to serialize coalesced");
+
+    let patch = coalesced_binary_patch(&base_bytes, &edited_bytes).expect("Failed to create patch");
+    let patched = apply_binary_patch(&base_bytes, &patch).expect("Failed to apply patch");
+    let patched = deserialize_coalesced(&patched).expect("Failed to deserialize coalesced");
+    assert_eq!(patched.version, 2);
+    assert!(patched.structurally_eq(&edited));
+}
+
+/// Tests that applying a structural patch keeps the version of the base
+/// coalesced rather than taking the patch version
+#[test]
+fn test_coalesced_apply_patch_keeps_version() {
+    let mut base = sample_coalesced();
+
+    let mut patch = Coalesced::new(2);
+    patch.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "sfxgame.sfxgamemodebase",
+        "speed",
+        "2.0",
+    );
+
+    base.apply_patch(&patch);
+    assert_eq!(base.version, 1);
+    assert_eq!(
+        base.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "sfxgame.sfxgamemodebase",
+            "speed"
+        ),
+        Some("2.0")
+    );
+}
+
+/// Tests that patches match section and property names ignoring ASCII
+/// case while keeping the casing of the existing names
+#[test]
+fn test_coalesced_patch_case_insensitive() {
+    let mut base = sample_coalesced();
+
+    let mut patch = Coalesced::new(1);
+    patch.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "SFXGame.SFXGameModeBase",
+            vec![property(
+                "Bindings",
+                vec![value(ValueType::Add, Some("Crouch"))],
+            )],
+        )],
+    ));
+
+    base.apply_patch(&patch);
+
+    let sections = &base.files[0].sections;
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].name, "sfxgame.sfxgamemodebase");
+    assert_eq!(
+        sections[0].properties[0],
+        property(
+            "bindings",
+            vec![
+                value(ValueType::Add, Some("Jump")),
+                value(ValueType::Add, Some("Crouch")),
+            ]
+        )
+    );
+
+    assert_eq!(
+        base.get(
+            "..\\BIOGame\\Config\\BIOGame.ini",
+            "SFXGAME.sfxgamemodebase",
+            "SPEED"
+        ),
+        Some("1.5")
+    );
+}
+
+/// Tests encoding and decoding text through the public huffman codec and
+/// that a codec from a serialized tree encodes values as they were written
+#[test]
+fn test_coalesced_huffman_codec() {
+    let codec = HuffmanCodec::new(["Shepard", "Normandy"]);
+
+    let (bytes, length) = encode_string(&codec, "Shepard").expect("Failed to encode");
+    assert_eq!(bytes.len(), length.div_ceil(8));
+    assert_eq!(
+        decode_string(&codec, &bytes).expect("Failed to decode"),
+        "Shepard"
+    );
+    let (bytes, _) = encode_string(&codec, "").expect("Failed to encode");
+    assert_eq!(decode_string(&codec, &bytes).expect("Failed to decode"), "");
+
+    // The packed bytes decode the same through the raw decoder
+    let symbols = huffman_decode_raw(&bytes, codec.pairs(), 0, usize::MAX, true, BitOrder::Lsb0)
+        .expect("Failed to decode");
+    assert!(symbols.is_empty());
+
+    // Characters outside the tree can't be encoded
+    assert!(encode_string(&codec, "Garrus").is_none());
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.set(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        "engine.engine",
+        "value",
+        "Normandy",
+    );
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let raw = deserialize_coalesced_raw(&bytes).expect("Failed to read raw coalesced");
+
+    let codec = HuffmanCodec::from_pairs(&raw.huffman_tree).expect("Invalid huffman tree");
+    let (bytes, length) = encode_string(&codec, "Normandy").expect("Failed to encode");
+    assert_eq!(length, raw.total_bits as usize);
+    assert_eq!(bytes, raw.data_block);
+}
+
+/// Tests that a table with more entries than its u16 count can store
+/// is an error rather than writing a wrapped count
+#[test]
+fn test_coalesced_too_many_entries() {
+    let count = u16::MAX as usize + 1;
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![property(
+                "value",
+                vec![value(ValueType::RemoveProperty, None); count],
+            )],
+        )],
+    ));
+
+    let err = serialize_coalesced(&coalesced).expect_err("Too many values should fail");
+    assert!(matches!(
+        err,
+        EncodeError::TooManyEntries {
+            kind: EntryKind::Values,
+            count: value_count
+        } if value_count == count
+    ));
+
+    // The largest count that fits is still accepted
+    coalesced.files[0].sections[0].properties[0].values.pop();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+    let decoded = deserialize_coalesced(&bytes).expect("Failed to deserialize coalesced");
+    assert_eq!(
+        decoded.files[0].sections[0].properties[0].values.len(),
+        count - 1
+    );
+}
+
+/// Tests that the max field name length declared by the header of a freshly
+/// serialized file matches the longest string table key
+#[test]
+fn test_coalesced_max_field_name_length() {
+    let coalesced = sample_coalesced();
+    let bytes = serialize_coalesced(&coalesced).expect("Failed to serialize coalesced");
+
+    let longest = coalesced
+        .string_table_keys()
+        .iter()
+        .map(String::len)
+        .max()
+        .expect("Missing keys");
+
+    let reader = CoalescedReader::new(&bytes).expect("Failed to create reader");
+    assert_eq!(reader.header.max_field_name_length as usize, longest);
+    assert_eq!(reader.max_key_length(), longest);
+
+    let header = parse_header(&bytes).expect("Failed to parse header");
+    assert_eq!(header.max_field_name_length as usize, longest);
+}
+
+/// Tests that more unique names than the index block can reference is
+/// an error rather than writing wrapped string table indexes
+#[test]
+fn test_coalesced_too_many_keys() {
+    // Split across two sections as a single section can't store this many properties
+    let properties = |range: std::ops::Range<usize>| -> Vec<Property> {
+        range
+            .map(|index| property(&format!("key_{}", index), vec![]))
+            .collect()
+    };
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![
+            section("engine.engine", properties(0..32768)),
+            section("engine.other", properties(32768..65536)),
+        ],
+    ));
+
+    // One file path, two section names, and 65536 property names
+    let err = serialize_coalesced(&coalesced).expect_err("Too many keys should fail");
+    assert!(matches!(err, EncodeError::TooManyKeys { count: 65539 }));
+}
+
+/// Tests that a name longer than its u16 length prefix can store is an
+/// error rather than writing a truncated length
+#[test]
+fn test_coalesced_name_too_long() {
+    let name = "a".repeat(u16::MAX as usize + 1);
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(file(
+        "..\\BIOGame\\Config\\BIOGame.ini",
+        vec![section(
+            "engine.engine",
+            vec![property(&name, vec![value(ValueType::New, Some("1"))])],
+        )],
+    ));
+
+    let err = serialize_coalesced(&coalesced).expect_err("Long name should fail");
+    assert!(matches!(err, EncodeError::NameTooLong { length } if length == name.len()));
+}
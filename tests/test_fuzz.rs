@@ -0,0 +1,142 @@
+use me3_coalesced_parser::{
+    deserialize_coalesced, deserialize_tlk, serialize_coalesced, serialize_tlk, verify_coalesced,
+    CoalFile, Coalesced, Property, Section, Tlk, TlkString, ValueType, ME3_MAGIC, TLK_MAGIC,
+};
+
+/// Small deterministic xorshift random number generator so
+/// failures can be reproduced
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self, length: usize) -> Vec<u8> {
+        (0..length).map(|_| self.next() as u8).collect()
+    }
+}
+
+/// Creates a coalesced to mutate
+fn sample_coalesced() -> Coalesced {
+    let mut property = Property {
+        name: "bindings".to_string(),
+        values: Vec::new(),
+    };
+    property.push_value(ValueType::Add, Some("Jump".to_string()));
+    property.push_value(ValueType::RemoveProperty, None);
+    property.push_value(ValueType::New, Some("Crouch".to_string()));
+
+    let mut coalesced = Coalesced::new(1);
+    coalesced.files.push(CoalFile {
+        path: "..\\BIOGame\\Config\\BIOInput.ini".to_string(),
+        sections: vec![Section {
+            name: "sfxgame.sfxgamemodebase".to_string(),
+            properties: vec![property],
+        }],
+    });
+    coalesced
+}
+
+/// Creates a tlk to mutate
+fn sample_tlk() -> Tlk {
+    let mut tlk = Tlk::new(3, 2);
+    tlk.male_values.push(TlkString {
+        id: 1,
+        value: "Shepard".encode_utf16().collect(),
+    });
+    tlk.female_values.push(TlkString {
+        id: 2,
+        value: "Commander".encode_utf16().collect(),
+    });
+    tlk
+}
+
+/// Tests that random bytes following a valid magic are rejected
+/// with an error rather than a panic
+#[test]
+fn test_fuzz_random_bytes() {
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+
+    for _ in 0..2000 {
+        let length = (rng.next() % 256) as usize;
+
+        let mut bytes = ME3_MAGIC.to_le_bytes().to_vec();
+        bytes.extend(rng.bytes(length));
+        assert!(deserialize_coalesced(&bytes).is_err());
+        assert!(verify_coalesced(&bytes).is_err());
+
+        let mut bytes = TLK_MAGIC.to_le_bytes().to_vec();
+        bytes.extend(rng.bytes(length));
+        assert!(deserialize_tlk(&bytes).is_err());
+    }
+}
+
+/// Tests that randomly mutated valid files never panic when decoded
+#[test]
+fn test_fuzz_mutated_files() {
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+
+    let coalesced =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+    let tlk = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+
+    for _ in 0..5000 {
+        for original in [&coalesced, &tlk] {
+            let mut bytes = original.clone();
+
+            // Mutate a few bytes keeping the magic intact
+            let mutations = 1 + rng.next() % 4;
+            for _ in 0..mutations {
+                let index = 4 + (rng.next() as usize % (bytes.len() - 4));
+                bytes[index] = rng.next() as u8;
+            }
+
+            // Sometimes truncate the file
+            if rng.next().is_multiple_of(4) {
+                let length = 4 + (rng.next() as usize % (bytes.len() - 4));
+                bytes.truncate(length);
+            }
+
+            _ = deserialize_coalesced(&bytes);
+            _ = verify_coalesced(&bytes);
+            _ = deserialize_tlk(&bytes);
+        }
+    }
+}
+
+/// Tests that files with an empty huffman tree are rejected
+#[test]
+fn test_fuzz_empty_huffman_tree() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+
+    // Huffman pair count follows the string table
+    let string_table_size = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+    let count_pos = 32 + string_table_size;
+    bytes[count_pos..count_pos + 2].copy_from_slice(&0u16.to_le_bytes());
+
+    assert!(deserialize_coalesced(&bytes).is_err());
+
+    let mut bytes = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+
+    // Tree node count within the header
+    bytes[20..24].copy_from_slice(&0u32.to_le_bytes());
+
+    assert!(deserialize_tlk(&bytes).is_err());
+}
+
+/// Tests that string table offsets near the maximum are rejected
+#[test]
+fn test_fuzz_string_table_offset_overflow() {
+    let mut bytes =
+        serialize_coalesced(&sample_coalesced()).expect("Failed to serialize coalesced");
+
+    // Offset of the first string table key
+    bytes[44..48].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert!(deserialize_coalesced(&bytes).is_err());
+}
@@ -3,7 +3,35 @@ use std::{
     io::{Read, Write},
 };
 
-use me3_coalesced_parser::{deserialize_tlk, serialize_tlk};
+use me3_coalesced_parser::{
+    deserialize_tlk,
+    error::{DecodeError, EncodeError},
+    serialize_tlk, serialize_tlk_reusing_tree, serialize_tlk_unchecked, string_to_wstring,
+    wstring_to_string, MergeStrategy, Tlk, TlkReader, TlkString,
+};
+
+/// Creates a tlk string from the provided UTF-8 text
+fn tlk_string(id: u32, value: &str) -> TlkString {
+    TlkString {
+        id,
+        value: value.encode_utf16().collect(),
+    }
+}
+
+/// Creates a small tlk for use in tests
+fn sample_tlk() -> Tlk {
+    Tlk {
+        version: 3,
+        min_version: 2,
+        male_values: vec![
+            tlk_string(1, "Shepard"),
+            tlk_string(2, "Commander"),
+            tlk_string(3, "Normandy"),
+        ],
+        female_values: vec![tlk_string(2, "Commander"), tlk_string(4, "Citadel")],
+        language: None,
+    }
+}
 
 /// Tests that a valid tlk can be parsed, encoded, and parsed again
 /// without any errors.
@@ -24,7 +52,7 @@ fn test_tlk_rebuild() {
     let tlk = deserialize_tlk(&bytes).expect("Failed to parse tlk");
 
     // Encode
-    let bytes = serialize_tlk(&tlk);
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
 
     // Parse
     let tlk = deserialize_tlk(&bytes).expect("Failed to parse tlk");
@@ -33,3 +61,452 @@ fn test_tlk_rebuild() {
     out.write_all(serde_json::to_string_pretty(&tlk).unwrap().as_bytes())
         .unwrap();
 }
+
+/// Tests looking up and removing tlk strings
+#[test]
+fn test_tlk_get_remove() {
+    let mut tlk = sample_tlk();
+
+    assert_eq!(tlk.get_male_utf8(1).as_deref(), Some("Shepard"));
+    assert_eq!(tlk.get_female_utf8(4).as_deref(), Some("Citadel"));
+    assert!(tlk.get_male(4).is_none());
+    assert!(tlk.get_female(1).is_none());
+
+    assert!(tlk.contains(1));
+    assert!(tlk.contains(4));
+    assert!(!tlk.contains(5));
+
+    assert!(tlk.remove_male(2));
+    assert!(!tlk.remove_male(2));
+    assert!(!tlk.remove_female(5));
+
+    // Remaining entries should keep their order
+    let ids: Vec<u32> = tlk.male_values.iter().map(|value| value.id).collect();
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(tlk.get_male_utf8(3).as_deref(), Some("Normandy"));
+
+    // Female table should be untouched
+    assert!(tlk.contains(2));
+    assert_eq!(tlk.get_female_utf8(2).as_deref(), Some("Commander"));
+}
+
+/// Tests that tlk strings with duplicate IDs are rejected when serializing
+#[test]
+fn test_tlk_duplicate_id() {
+    let mut tlk = sample_tlk();
+    assert!(tlk.validate().is_ok());
+
+    // The same ID across the male and female tables is allowed
+    tlk.female_values.push(tlk_string(1, "Shepard"));
+    assert!(serialize_tlk(&tlk).is_ok());
+
+    tlk.male_values.push(tlk_string(3, "SSV Normandy"));
+    let err = serialize_tlk(&tlk).expect_err("Duplicate ID should fail");
+    assert!(matches!(err, EncodeError::DuplicateTlkId { id: 3 }));
+
+    // Unchecked serialize keeps both entries
+    let bytes = serialize_tlk_unchecked(&tlk);
+    let tlk = deserialize_tlk(&bytes).expect("Failed to parse tlk");
+    assert_eq!(tlk.male_values.len(), 4);
+}
+
+/// Tests that strings read lazily through the tlk reader match
+/// the strings from eagerly decoding the tlk
+#[test]
+fn test_tlk_reader() {
+    let bytes = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+    let tlk = deserialize_tlk(&bytes).expect("Failed to parse tlk");
+    let reader = TlkReader::new(&bytes).expect("Failed to read tlk");
+
+    assert_eq!(reader.version, tlk.version);
+    assert_eq!(reader.min_version, tlk.min_version);
+
+    let value = reader.get(3).expect("Failed to decode string");
+    assert_eq!(value.as_deref(), tlk.get_male(3));
+
+    let value = reader.get_female(4).expect("Failed to decode string");
+    assert_eq!(value.as_deref(), tlk.get_female(4));
+
+    assert!(reader.get(5).expect("Failed to decode string").is_none());
+
+    let male: Vec<_> = reader
+        .iter_male()
+        .collect::<Result<_, _>>()
+        .expect("Failed to decode strings");
+    assert_eq!(male.len(), tlk.male_values.len());
+    for (lazy, eager) in male.iter().zip(tlk.male_values.iter()) {
+        assert_eq!(lazy.id, eager.id);
+        assert_eq!(lazy.value, eager.value);
+    }
+}
+
+/// Tests converting between UTF-16 and UTF-8 strings
+#[test]
+fn test_wstring_conversion() {
+    // Basic multilingual plane
+    let value = string_to_wstring("Shepard");
+    assert_eq!(value.len(), 7);
+    assert_eq!(wstring_to_string(&value), "Shepard");
+
+    // Astral plane characters are encoded as surrogate pairs
+    let value = string_to_wstring("N7 🚀");
+    assert_eq!(value, vec![0x4E, 0x37, 0x20, 0xD83D, 0xDE80]);
+    assert_eq!(wstring_to_string(&value), "N7 🚀");
+
+    // Lone surrogates are replaced
+    assert_eq!(wstring_to_string(&[0x41, 0xD83D, 0x42]), "A\u{FFFD}B");
+}
+
+/// Tests that the language is kept in JSON but not written to tlk files
+#[cfg(feature = "json")]
+#[test]
+fn test_tlk_language() {
+    use me3_coalesced_parser::{tlk_from_json, tlk_to_json_pretty};
+
+    let tlk = sample_tlk().with_language("INT".to_string());
+
+    let json = tlk_to_json_pretty(&tlk);
+    let parsed = tlk_from_json(&json).expect("Failed to parse json");
+    assert_eq!(parsed.language.as_deref(), Some("INT"));
+
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+    let untagged = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+    assert_eq!(bytes.len(), untagged.len());
+
+    let parsed = deserialize_tlk(&bytes).expect("Failed to parse tlk");
+    assert!(parsed.language.is_none());
+}
+
+/// Tests that a tlk without any strings can be serialized and parsed
+#[test]
+fn test_tlk_empty() {
+    let tlk = Tlk::new(3, 2);
+
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize empty tlk");
+    let parsed = deserialize_tlk(&bytes).expect("Failed to parse empty tlk");
+
+    assert!(parsed.male_values.is_empty());
+    assert!(parsed.female_values.is_empty());
+}
+
+/// Tests that a string offset past the end of the data block is
+/// reported rather than decoding an empty string
+#[test]
+fn test_tlk_offset_past_data() {
+    let mut bytes = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+
+    // Bit offset of the first male string follows its ID after the header
+    bytes[32..36].copy_from_slice(&0x00FF_FFFFu32.to_le_bytes());
+
+    let err = deserialize_tlk(&bytes).expect_err("Offset past data should fail");
+    assert!(matches!(
+        err,
+        DecodeError::InvalidOffset {
+            offset: 0x00FF_FFFF,
+            ..
+        }
+    ));
+}
+
+/// Tests that a huge string count over a tiny buffer fails cleanly
+/// rather than attempting to allocate for every declared string
+#[test]
+fn test_tlk_huge_string_count() {
+    let mut bytes = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+
+    // Male string count within the header
+    bytes[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let err = deserialize_tlk(&bytes).expect_err("Huge string count should fail");
+    assert!(matches!(err, DecodeError::UnexpectedEof { .. }));
+}
+
+/// Tests that strings sharing the same bit offset are decoded correctly
+/// both with and without the decode cache
+#[test]
+fn test_tlk_shared_offsets() {
+    let mut bytes = serialize_tlk(&sample_tlk()).expect("Failed to serialize tlk");
+
+    // Point both female strings at the offsets of male strings, the refs
+    // are (id, offset) pairs following the header
+    let male_offset = |bytes: &[u8], index: usize| -> [u8; 4] {
+        let start = 28 + index * 8 + 4;
+        bytes[start..start + 4].try_into().unwrap()
+    };
+    let commander = male_offset(&bytes, 1);
+    let shepard = male_offset(&bytes, 0);
+    bytes[56..60].copy_from_slice(&commander);
+    bytes[64..68].copy_from_slice(&shepard);
+
+    let expected = deserialize_tlk(&bytes).expect("Failed to deserialize tlk");
+    assert_eq!(
+        expected.male_values[1].value,
+        tlk_string(2, "Commander").value
+    );
+    assert_eq!(
+        expected.female_values[0].value,
+        tlk_string(2, "Commander").value
+    );
+    assert_eq!(
+        expected.female_values[1].value,
+        tlk_string(4, "Shepard").value
+    );
+
+    let reader = TlkReader::with_cache(&bytes).expect("Failed to read tlk");
+    for _ in 0..2 {
+        let male: Vec<TlkString> = reader
+            .iter_male()
+            .collect::<Result<_, _>>()
+            .expect("Failed to decode strings");
+        let female: Vec<TlkString> = reader
+            .iter_female()
+            .collect::<Result<_, _>>()
+            .expect("Failed to decode strings");
+
+        assert_eq!(male, expected.male_values);
+        assert_eq!(female, expected.female_values);
+    }
+}
+
+/// Gets the data block of a serialized tlk
+fn tlk_data_block(bytes: &[u8]) -> &[u8] {
+    let length = u32::from_le_bytes(bytes[24..28].try_into().unwrap()) as usize;
+    &bytes[bytes.len() - length..]
+}
+
+/// Tests that reusing the huffman tree of a previous serialization keeps
+/// the encoding of unchanged strings stable and that a new tree is built
+/// when the previous tree can't encode every character
+#[test]
+fn test_tlk_reusing_tree() {
+    let tlk = sample_tlk();
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+    let reader = TlkReader::new(&bytes).expect("Failed to read tlk");
+    let tree = reader.huffman_tree().to_vec();
+
+    // Edit the last male string using only characters already in the tree
+    let mut edited = sample_tlk();
+    edited.replace_male_utf8(3, "Nomad".to_string());
+
+    let reused = serialize_tlk_reusing_tree(&edited, &tree).expect("Failed to serialize tlk");
+    let reused_reader = TlkReader::new(&reused).expect("Failed to read tlk");
+    assert_eq!(reused_reader.huffman_tree(), tree.as_slice());
+
+    // The strings before the edited string are encoded the same
+    let edited_offset = u32::from_le_bytes(bytes[48..52].try_into().unwrap()) as usize;
+    let unchanged = edited_offset / 8;
+    assert_eq!(
+        tlk_data_block(&bytes)[..unchanged],
+        tlk_data_block(&reused)[..unchanged]
+    );
+
+    let parsed = deserialize_tlk(&reused).expect("Failed to parse tlk");
+    assert_eq!(parsed.get_male_utf8(3).as_deref(), Some("Nomad"));
+    assert_eq!(parsed.get_female_utf8(4).as_deref(), Some("Citadel"));
+
+    // Characters missing from the tree require a new tree
+    edited.replace_male_utf8(3, "Zaeed".to_string());
+    let rebuilt = serialize_tlk_reusing_tree(&edited, &tree).expect("Failed to serialize tlk");
+    let parsed = deserialize_tlk(&rebuilt).expect("Failed to parse tlk");
+    assert_eq!(parsed.get_male_utf8(3).as_deref(), Some("Zaeed"));
+}
+
+/// Tests sorting the tlk strings by ID keeps each string with its ID
+#[test]
+fn test_tlk_sort() {
+    let mut tlk = Tlk::new(3, 2);
+    tlk.male_values = vec![
+        tlk_string(3, "Normandy"),
+        tlk_string(1, "Shepard"),
+        tlk_string(2, "Commander"),
+    ];
+    tlk.female_values = vec![tlk_string(4, "Citadel"), tlk_string(2, "Commander")];
+
+    let ids: Vec<u32> = tlk.sorted_male().iter().map(|value| value.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    let ids: Vec<u32> = tlk.sorted_female().iter().map(|value| value.id).collect();
+    assert_eq!(ids, vec![2, 4]);
+
+    // The stored order is unchanged until sorted
+    assert_eq!(tlk.male_values[0].id, 3);
+
+    tlk.sort();
+    assert_eq!(
+        tlk.male_values,
+        vec![
+            tlk_string(1, "Shepard"),
+            tlk_string(2, "Commander"),
+            tlk_string(3, "Normandy"),
+        ]
+    );
+    assert_eq!(
+        tlk.female_values,
+        vec![tlk_string(2, "Commander"), tlk_string(4, "Citadel")]
+    );
+}
+
+/// Tests combining the male and female strings by ID
+#[test]
+fn test_tlk_into_combined() {
+    let combined = sample_tlk().into_combined();
+
+    assert_eq!(combined.len(), 4);
+    assert_eq!(combined[&1], (Some("Shepard".to_string()), None));
+    assert_eq!(
+        combined[&2],
+        (Some("Commander".to_string()), Some("Commander".to_string()))
+    );
+    assert_eq!(combined[&3], (Some("Normandy".to_string()), None));
+    assert_eq!(combined[&4], (None, Some("Citadel".to_string())));
+}
+
+/// Creates a tlk with IDs overlapping those of [sample_tlk]
+fn overlapping_tlk() -> Tlk {
+    let mut tlk = Tlk::new(3, 2);
+    tlk.male_values = vec![tlk_string(3, "SR-2"), tlk_string(5, "Garrus")];
+    tlk.female_values = vec![tlk_string(4, "Omega"), tlk_string(6, "Tali")];
+    tlk
+}
+
+/// Tests merging overlapping tlks keeps the existing strings
+#[test]
+fn test_tlk_merge_keep_existing() {
+    let mut tlk = sample_tlk();
+    tlk.merge(overlapping_tlk(), MergeStrategy::KeepExisting)
+        .expect("Merge should succeed");
+
+    assert_eq!(
+        tlk.male_values,
+        vec![
+            tlk_string(1, "Shepard"),
+            tlk_string(2, "Commander"),
+            tlk_string(3, "Normandy"),
+            tlk_string(5, "Garrus"),
+        ]
+    );
+    assert_eq!(
+        tlk.female_values,
+        vec![
+            tlk_string(2, "Commander"),
+            tlk_string(4, "Citadel"),
+            tlk_string(6, "Tali"),
+        ]
+    );
+}
+
+/// Tests merging overlapping tlks replaces the existing strings
+#[test]
+fn test_tlk_merge_overwrite() {
+    let mut tlk = sample_tlk();
+    tlk.merge(overlapping_tlk(), MergeStrategy::Overwrite)
+        .expect("Merge should succeed");
+
+    assert_eq!(
+        tlk.male_values,
+        vec![
+            tlk_string(1, "Shepard"),
+            tlk_string(2, "Commander"),
+            tlk_string(3, "SR-2"),
+            tlk_string(5, "Garrus"),
+        ]
+    );
+    assert_eq!(
+        tlk.female_values,
+        vec![
+            tlk_string(2, "Commander"),
+            tlk_string(4, "Omega"),
+            tlk_string(6, "Tali"),
+        ]
+    );
+}
+
+/// Tests merging overlapping tlks fails on the first conflicting ID
+/// and leaves the tlk unchanged
+#[test]
+fn test_tlk_merge_error() {
+    let mut tlk = sample_tlk();
+    let err = tlk
+        .merge(overlapping_tlk(), MergeStrategy::Error)
+        .expect_err("Overlapping IDs should fail");
+    assert!(matches!(err, EncodeError::DuplicateTlkId { id: 3 }));
+    assert_eq!(tlk, sample_tlk());
+
+    // The male and female strings are checked independently
+    let mut other = Tlk::new(3, 2);
+    other.male_values = vec![tlk_string(4, "Omega")];
+    tlk.merge(other, MergeStrategy::Error)
+        .expect("IDs only shared across tables should merge");
+    assert_eq!(tlk.get_male_utf8(4).as_deref(), Some("Omega"));
+}
+
+/// Tests that text with characters outside the BMP is inserted as surrogate
+/// pairs and round trips through serialization
+#[test]
+fn test_tlk_insert_emoji() {
+    let text = "Shepard \u{1F680} Normandy";
+
+    let mut tlk = Tlk::new(3, 2);
+    tlk.insert_male_utf8(1, text.to_string());
+    tlk.insert_female_utf8(1, text.to_string());
+
+    // The rocket is stored as a surrogate pair
+    let value = tlk.get_male(1).expect("Missing male string");
+    assert_eq!(value.len(), text.chars().count() + 1);
+    assert_eq!(&value[8..10], &[0xD83D, 0xDE80]);
+
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+    let decoded = deserialize_tlk(&bytes).expect("Failed to deserialize tlk");
+
+    assert_eq!(decoded.get_male_utf8(1).as_deref(), Some(text));
+    assert_eq!(decoded.get_female_utf8(1).as_deref(), Some(text));
+}
+
+/// Tests that identical strings share the same offset within the
+/// data block rather than being encoded again
+#[test]
+fn test_tlk_dedup_offsets() {
+    let tlk = sample_tlk();
+    let bytes = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+
+    // Male "Commander" is the second male ref and female "Commander"
+    // is the first female ref following the three male refs
+    assert_eq!(bytes[40..44], bytes[56..60]);
+    assert_ne!(bytes[32..36], bytes[64..68]);
+
+    let decoded = deserialize_tlk(&bytes).expect("Failed to deserialize tlk");
+    assert_eq!(decoded, tlk);
+
+    // Duplicating every male string as a female string doesn't grow the data
+    let mut duplicated = tlk.clone();
+    duplicated.female_values = tlk.male_values.clone();
+    let mut unique = tlk.clone();
+    unique.female_values.clear();
+
+    let duplicated = serialize_tlk(&duplicated).expect("Failed to serialize tlk");
+    let unique = serialize_tlk(&unique).expect("Failed to serialize tlk");
+    assert_eq!(tlk_data_block(&duplicated), tlk_data_block(&unique));
+}
+
+/// Tests that re-serializing a vanilla tlk produces a file close
+/// to the size of the original
+#[test]
+fn test_tlk_vanilla_size() {
+    // Only run test if valid tlk is present
+    if std::fs::metadata("./private/en.tlk").is_err() {
+        println!("Skipping tlk size test");
+        return;
+    }
+
+    let bytes = std::fs::read("./private/en.tlk").expect("Failed to read tlk");
+    let tlk = deserialize_tlk(&bytes).expect("Failed to parse tlk");
+    let serialized = serialize_tlk(&tlk).expect("Failed to serialize tlk");
+
+    // Allow 2% difference for the differently built huffman tree
+    let tolerance = bytes.len() / 50;
+    assert!(
+        serialized.len().abs_diff(bytes.len()) <= tolerance,
+        "Serialized size {} differs from vanilla size {}",
+        serialized.len(),
+        bytes.len()
+    );
+}